@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crossterm::{execute, terminal::SetTitle};
+use system_tray::item::Status;
+
+use crate::{
+    app::{App, AppResult},
+    config::OscNotification,
+    dbus,
+    wrappers::GetTitle,
+    CMD,
+};
+
+/// Saves the terminal title on the terminal's title stack (XTWINOPS).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+
+/// Restores the title saved with [PUSH_TITLE].
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Writes an OSC sequence made of the given parameters.
+///
+/// Control characters and `;` are removed from the parameters, so text coming
+/// from other D-Bus clients can't end the sequence early or inject other escapes.
+pub fn write_osc(out: &mut impl Write, params: &[&str]) -> io::Result<()> {
+    let params: Vec<String> = params
+        .iter()
+        .map(|param| {
+            param
+                .chars()
+                .filter(|c| !c.is_control() && *c != ';')
+                .collect()
+        })
+        .collect();
+    write!(out, "\x1b]{}\x07", params.join(";"))
+}
+
+/// Tray item that just started needing attention.
+struct Attention {
+    title: String,
+    icon: String,
+}
+
+/// Runs the configured [crate::config::Alerts] when tray items start needing attention.
+#[derive(Debug, Default)]
+pub struct Alerter {
+    /// last seen status by item address
+    statuses: HashMap<String, Status>,
+    /// number of items needing attention shown in the terminal title
+    title_count: Option<usize>,
+}
+
+impl Alerter {
+    /// Starts from the statuses the items already have, so they don't alert on startup.
+    pub fn new(app: &App) -> Self {
+        let statuses = app
+            .get_items()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|(key, (item, _))| (key.to_owned(), item.status))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            statuses,
+            title_count: None,
+        }
+    }
+
+    /// Compares item statuses with the last update and alerts about the new attention requests.
+    pub async fn update(&mut self, app: &App) -> AppResult<()> {
+        let alerts = &app.config.alerts;
+        let mut new = Vec::new();
+        let mut title_count = 0;
+        let mut any_title = false;
+
+        if let Some(items) = app.get_items() {
+            self.statuses.retain(|key, _| items.contains_key(key));
+            for (key, (item, _)) in items.iter() {
+                let previous = self.statuses.insert(key.to_owned(), item.status);
+                // Statuses of hidden items are still kept, so showing them again doesn't alert
+                if app.hidden.contains(&item.id) {
+                    continue;
+                }
+
+                let actions = alerts.actions(&item.id);
+                any_title |= actions.title;
+                let attention = item.status == Status::NeedsAttention;
+                if attention && actions.title {
+                    title_count += 1;
+                }
+
+                if attention && previous != Some(Status::NeedsAttention) {
+                    let icon = item
+                        .attention_icon_name
+                        .clone()
+                        .filter(|name| !name.is_empty())
+                        .or_else(|| item.icon_name.clone())
+                        .unwrap_or_default();
+                    new.push((
+                        actions,
+                        Attention {
+                            title: item.get_title().to_owned(),
+                            icon,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let mut out = io::stdout();
+        if any_title && self.title_count != Some(title_count) {
+            let title = match title_count {
+                0 => String::from(CMD),
+                count => format!("{CMD} ({count})"),
+            };
+            if self.title_count.is_none() {
+                write!(out, "{PUSH_TITLE}")?;
+            }
+            execute!(out, SetTitle(title))?;
+            self.title_count = Some(title_count);
+        }
+
+        for (actions, attention) in new {
+            log::info!("{} needs attention", attention.title);
+            if actions.bell {
+                write!(out, "\x07")?;
+            }
+            let message = format!("{} needs attention", attention.title);
+            match actions.osc_notification {
+                OscNotification::Osc9 => write_osc(&mut out, &["9", &message])?,
+                OscNotification::Osc777 => write_osc(&mut out, &["777", "notify", CMD, &message])?,
+                OscNotification::None => {}
+            }
+            out.flush()?;
+
+            if actions.notification {
+                if let Err(e) =
+                    dbus::notify(&app.connection, &attention.title, &message, &attention.icon).await
+                {
+                    log::warn!("Failed to send notification: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restores the terminal title if it was changed.
+    pub fn restore_title(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.title_count.is_some() {
+            write!(out, "{POP_TITLE}")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
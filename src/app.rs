@@ -2,21 +2,31 @@ use indexmap::IndexMap;
 use ratatui::layout::{Position, Rect};
 use std::{
     cell::{Ref, RefMut},
-    collections::HashMap,
-    error,
+    collections::HashSet,
+    error, io,
     sync::{Arc, Mutex, MutexGuard},
 };
 use system_tray::client::ActivateRequest;
 use system_tray::{
     client::{Client, Event},
-    item::StatusNotifierItem,
+    data::BaseMap,
+    item::Status,
     menu::TrayMenu,
 };
 use tui_tree_widget::TreeState;
 
 use tokio::sync::broadcast::Receiver;
-
-use crate::wrappers::{FindMenuByUsize, GetTitle, Id, SniState};
+use zbus::Connection;
+
+use crate::config::PassiveItems;
+use crate::dbus::{self, ScrollOrientation};
+use crate::entries::menu_entries;
+use crate::graphics::ImageLayer;
+use crate::icon::IconCache;
+use crate::inspector::{self, Field, Inspector};
+use crate::palette::Palette;
+use crate::search::{search, Search, SearchResult};
+use crate::wrappers::{FindMenuById, GetAction, GetTitle, Id, ItemAction, MenuItemKind, SniState};
 use crate::Config;
 
 pub type BoxStack = Vec<(i32, Rect)>;
@@ -24,23 +34,13 @@ pub type BoxStack = Vec<(i32, Rect)>;
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Layout {
     pub rows: Vec<Vec<usize>>,
     pub last_col: usize,
     pub scroll_offset: u16,
 }
 
-impl Layout {
-    pub fn new() -> Self {
-        Self {
-            rows: Vec::default(),
-            last_col: 0,
-            scroll_offset: 0,
-        }
-    }
-}
-
 /// Application.
 #[derive(Debug)]
 pub struct App {
@@ -49,6 +49,8 @@ pub struct App {
     pub config: Config,
     /// system-tray client
     pub client: Client,
+    /// dbus connection for item methods the client doesn't expose
+    pub connection: Connection,
     /// states saved for each [StatusNotifierItem] and their [TrayMenu]
     pub sni_states: IndexMap<String, SniState>, // for the StatusNotifierItem
     //  currently focused sni item info
@@ -57,14 +59,25 @@ pub struct App {
     pub last_focused_sni_index: usize,
     pub focused_sni_key: String,
     /// items map from system-tray
-    pub items: Arc<Mutex<HashMap<String, (StatusNotifierItem, Option<TrayMenu>)>>>,
+    pub items: Arc<Mutex<BaseMap>>,
     pub tray_rx: Mutex<Receiver<Event>>,
     pub layout: Layout,
+    /// how key presses are interpreted
+    pub mode: Mode,
+    /// icons of the tray items
+    pub icons: IconCache,
+    /// icon images drawn over the frame
+    pub images: ImageLayer,
+    /// ids of the items hidden by the user
+    pub hidden: HashSet<String>,
 }
 
 impl App {
     /// Constructs a new instance of [`App`].
-    pub fn new(client: Client, config: Config) -> Self {
+    pub fn new(client: Client, connection: Connection, config: Config) -> Self {
+        let protocol = config.graphics.protocol();
+        log::info!("Drawing icons with {:?}", protocol);
+        let icons = IconCache::new(protocol, &config.icon_theme);
         Self {
             running: true,
             config,
@@ -72,10 +85,15 @@ impl App {
             items: client.items(),
             sni_states: IndexMap::default(),
             client,
+            connection,
             focused_sni_index: 0,
             last_focused_sni_index: 0,
             focused_sni_key: String::default(),
-            layout: Layout::new(),
+            layout: Layout::default(),
+            mode: Mode::default(),
+            icons,
+            images: ImageLayer::new(protocol),
+            hidden: HashSet::new(),
         }
     }
 
@@ -86,20 +104,23 @@ impl App {
             self.focused_sni_key = key.to_owned();
         }
 
-        // create a buffer for items keys and their titles(for sorting)
+        // create a buffer for items keys, their titles and statuses (for sorting)
+        let passive_items = self.config.passive_items;
         let mut buffer = IndexMap::new();
         if let Some(items) = self.get_items() {
             buffer = items
                 .iter()
-                .map(|(k, v)| (k.to_owned(), v.0.get_title().to_owned()))
+                .filter(|(_, v)| {
+                    passive_items != PassiveItems::Hide || v.0.status != Status::Passive
+                })
+                .filter(|(_, v)| !self.hidden.contains(&v.0.id))
+                .map(|(k, v)| (k.to_owned(), (v.0.get_title().to_owned(), v.0.status)))
                 .collect();
         }
 
         // Add sni states if there are in new items
         for (key, _) in &buffer {
-            self.sni_states
-                .entry(key.to_owned())
-                .or_insert_with(|| SniState::new());
+            self.sni_states.entry(key.to_owned()).or_default();
         }
 
         // Remove states that aren't in new items
@@ -108,7 +129,14 @@ impl App {
         // Sort by titles
         if self.config.sorting {
             self.sni_states
-                .sort_by(|k1, _, k2, _| buffer[k1].cmp(&buffer[k2]));
+                .sort_by(|k1, _, k2, _| buffer[k1].0.cmp(&buffer[k2].0));
+        }
+
+        // Move passive items to the end, keeping their order
+        if passive_items == PassiveItems::End {
+            let passive = |k: &String| buffer[k].1 == Status::Passive;
+            self.sni_states
+                .sort_by(|k1, _, k2, _| passive(k1).cmp(&passive(k2)));
         }
 
         // sync index to key back
@@ -116,7 +144,9 @@ impl App {
             self.focused_sni_index = index;
         } else if !self.sni_states.is_empty() {
             // Key is gone! Reset to a valid neighbor (next or previous)
-            self.focused_sni_index = self.focused_sni_index.min(self.sni_states.len().saturating_sub(1));
+            self.focused_sni_index = self
+                .focused_sni_index
+                .min(self.sni_states.len().saturating_sub(1));
             if let Some((k, _)) = self.sni_states.get_index(self.focused_sni_index) {
                 self.focused_sni_key = k.clone();
             }
@@ -134,18 +164,59 @@ impl App {
         self.sync_focus();
     }
 
+    /// Hides the item with the given id, or the focused one.
+    pub fn hide_item(&mut self, id: Option<&str>) -> Option<()> {
+        let id = match id {
+            Some(id) => id.to_owned(),
+            None => {
+                let key = self.get_focused_sni_key()?;
+                self.get_items()?.get(key)?.0.id.clone()
+            }
+        };
+        self.hidden.insert(id);
+        self.update();
+        Some(())
+    }
+
+    /// Shows the hidden item with the given id again, or all of them.
+    pub fn unhide_items(&mut self, id: Option<&str>) {
+        match id {
+            Some(id) => {
+                self.hidden.remove(id);
+            }
+            None => self.hidden.clear(),
+        }
+        self.update();
+    }
+
+    /// Sets the number of columns of the layout.
+    pub fn set_columns(&mut self, columns: usize) {
+        self.config.columns = columns.max(1);
+        self.update();
+    }
+
+    /// Loads the config file again, replacing the current config.
+    pub fn reload_config(&mut self) -> AppResult<()> {
+        let config = Config::new(&self.config.path)?;
+
+        let protocol = config.graphics.protocol();
+        self.images.clear(&mut io::stdout())?;
+        self.images = ImageLayer::new(protocol);
+        self.icons = IconCache::new(protocol, &config.icon_theme);
+        self.config = config;
+        self.update();
+
+        log::info!("Reloaded config");
+        Ok(())
+    }
+
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 
-    pub fn get_items(
-        &self,
-    ) -> Option<MutexGuard<'_, HashMap<String, (StatusNotifierItem, Option<TrayMenu>)>>> {
-        match self.items.lock() {
-            Ok(items) => Some(items),
-            Err(_) => None,
-        }
+    pub fn get_items(&self) -> Option<MutexGuard<'_, BaseMap>> {
+        self.items.lock().ok()
     }
 
     pub fn get_focused_sni_key(&self) -> Option<&String> {
@@ -160,12 +231,12 @@ impl App {
 
     pub fn get_focused_sni_state(&self) -> Option<&SniState> {
         let (_, v) = self.sni_states.get_index(self.focused_sni_index)?;
-        return Some(v);
+        Some(v)
     }
 
     pub fn get_focused_sni_state_mut(&mut self) -> Option<&mut SniState> {
         let (_, v) = self.sni_states.get_index_mut(self.focused_sni_index)?;
-        return Some(v);
+        Some(v)
     }
 
     pub fn get_focused_sni_key_by_position(&mut self, pos: Position) -> Option<String> {
@@ -219,14 +290,22 @@ impl App {
 
             FocusDirection::Up => {
                 let target_row = if row == 0 { last_row_index } else { row - 1 };
-                let target_len = if target_row == last_row_index { last_row_len } else { cols };
+                let target_len = if target_row == last_row_index {
+                    last_row_len
+                } else {
+                    cols
+                };
                 let clamped_col = self.layout.last_col.min(target_len - 1);
                 target_row * cols + clamped_col
             }
 
             FocusDirection::Down => {
                 let target_row = if row == last_row_index { 0 } else { row + 1 };
-                let target_len = if target_row == last_row_index { last_row_len } else { cols };
+                let target_len = if target_row == last_row_index {
+                    last_row_len
+                } else {
+                    cols
+                };
                 let clamped_col = self.layout.last_col.min(target_len - 1);
                 target_row * cols + clamped_col
             }
@@ -234,10 +313,9 @@ impl App {
 
         match direction {
             FocusDirection::Up | FocusDirection::Down => self.layout.last_col = col,
-            _ => self.layout.last_col = new_index % cols
+            _ => self.layout.last_col = new_index % cols,
         }
 
-
         if let Some((key, _)) = self.sni_states.get_index(new_index) {
             let key = key.clone();
             self.focused_sni_index = new_index;
@@ -258,48 +336,403 @@ impl App {
         }
     }
 
-    pub async fn activate_menu_item(
-        &self,
-        ids: &[Id],
-        tree_state: &mut TreeState<Id>,
-    ) -> Option<()> {
+    /// Moves the selection inside the focused menu up or down, skipping separators.
+    pub fn move_menu_selection(&self, direction: FocusDirection) -> Option<()> {
+        let sni_key = self.get_focused_sni_key()?;
+        let map = self.get_items()?;
+        let menu = map.get(sni_key)?.1.as_ref()?;
+        let mut tree_state = self.get_focused_tree_state_mut()?;
+
+        move_selection(&mut tree_state, menu, direction);
+
+        Some(())
+    }
+
+    /// Selects an entry of the focused menu, unless it is a separator.
+    pub fn select_menu_item(&self, ids: Vec<Id>) -> Option<()> {
+        let sni_key = self.get_focused_sni_key()?;
+        let map = self.get_items()?;
+        let item = map.get(sni_key)?.1.as_ref()?.find_menu_by_id(&ids)?;
+        if item.is_separator() {
+            return None;
+        }
+        self.get_focused_tree_state_mut()?.select(ids);
+
+        Some(())
+    }
+
+    pub async fn activate_menu_item(&self, ids: &[Id]) -> Option<()> {
         log::debug!("Entered activate_menu_item");
         let sni_key = self.get_focused_sni_key()?;
         log::debug!("Activating menu item with key: {}", &sni_key);
-        let map = self.get_items()?;
-        let (sni, menu) = map.get(sni_key)?;
-        let menu = match menu {
-            Some(menu) => menu,
-            None => return None,
+
+        // Resolve everything needed from the items map up front,
+        // so that the lock isn't held while waiting on dbus.
+        let (path, submenu_id, is_submenu) = {
+            let map = self.get_items()?;
+            let (sni, menu) = map.get(sni_key)?;
+            let item = menu.as_ref()?.find_menu_by_id(ids)?;
+
+            if !item.is_activatable() {
+                log::debug!("Menu item {} can't be activated", item.id);
+                return None;
+            }
+
+            if item.is_submenu() {
+                let mut tree_state = self.get_focused_tree_state_mut()?;
+                tree_state.toggle(ids.to_vec());
+                if !tree_state.opened().contains(ids) {
+                    return Some(());
+                }
+            }
+
+            (sni.menu.clone()?, item.id, item.is_submenu())
         };
 
-        let item = menu.find_menu_by_usize(ids)?;
+        if is_submenu {
+            // Some apps only populate their submenus once they are about to be shown,
+            // the client fills them in when the layout update arrives
+            let res = self
+                .client
+                .about_to_show_menuitem(sni_key.to_string(), path, submenu_id)
+                .await;
+            log::debug!("Result of showing submenu {}: {:?}", submenu_id, res);
+            return Some(());
+        }
+
+        let activate_request = ActivateRequest::MenuItem {
+            address: sni_key.to_string(),
+            menu_path: path.clone(),
+            submenu_id,
+        };
+        let res = self.client.activate(activate_request).await;
+        log::debug!("Result of activating an item: {:?}", res);
+
+        let _ = self
+            .client
+            .about_to_show_menuitem(sni_key.to_string(), path, 0)
+            .await;
+
+        Some(())
+    }
+
+    /// Enters mnemonic mode in the focused menu, scoped to the submenu holding the selection.
+    pub fn enter_mnemonic_mode(&mut self) -> Option<()> {
+        let sni_key = self.get_focused_sni_key()?;
+        if !self.get_items()?.get(sni_key)?.has_menu() {
+            return None;
+        }
+
+        let mut scope = self.get_focused_tree_state()?.selected().to_vec();
+        scope.pop();
+        self.mode = Mode::Mnemonic(scope);
+
+        Some(())
+    }
+
+    /// Opens the inspector for the focused item.
+    pub fn open_inspector(&mut self) -> Option<()> {
+        let key = self.get_focused_sni_key()?.to_owned();
+        self.mode = Mode::Inspector(Inspector::new(key));
+        Some(())
+    }
 
-        if item.submenu.is_empty() {
-            if let Some(path) = &sni.menu {
-                let activate_request = ActivateRequest::MenuItem {
+    /// Fields of the item shown in the inspector, `None` once the item is gone.
+    pub fn inspector_fields(&self, inspector: &Inspector) -> Option<Vec<Field>> {
+        let items = self.get_items()?;
+        Some(inspector::fields(
+            &inspector.key,
+            items.get(&inspector.key)?,
+        ))
+    }
+
+    /// Menu entries of the shown items matching the query, best matches first.
+    pub fn search_results(&self, query: &str) -> Vec<SearchResult> {
+        let Some(items) = self.get_items() else {
+            return Vec::new();
+        };
+        let entries = self
+            .sni_states
+            .keys()
+            .filter_map(|key| Some(menu_entries(key, items.get(key)?)))
+            .flatten()
+            .collect();
+        search(entries, query)
+    }
+
+    /// Focuses the item, reveals the menu entry at `ids` and activates it.
+    pub async fn activate_menu_entry(&mut self, sni_key: &str, ids: &[Id]) -> Option<()> {
+        let index = self.sni_states.get_index_of(sni_key)?;
+        if let Some(state) = self.get_focused_sni_state_mut() {
+            state.set_focused(false);
+        }
+        self.focused_sni_index = index;
+        self.focused_sni_key = sni_key.to_owned();
+        self.sync_focus();
+
+        {
+            let mut tree_state = self.get_focused_tree_state_mut()?;
+            for depth in 1..ids.len() {
+                tree_state.open(ids[..depth].to_vec());
+            }
+            tree_state.select(ids.to_vec());
+            // Activating toggles submenus, a submenu found by search should always end up open
+            tree_state.close(ids);
+        }
+        self.activate_menu_item(ids).await
+    }
+
+    /// Selects and activates the entry of the submenu at `scope` with the given access key.
+    ///
+    /// Returns the path of the entry when it is a submenu, which is opened instead.
+    pub async fn activate_mnemonic(&self, scope: &[Id], key: char) -> Option<Vec<Id>> {
+        let sni_key = self.get_focused_sni_key()?;
+        let (ids, is_submenu) = {
+            let map = self.get_items()?;
+            let menu = map.get(sni_key)?.1.as_ref()?;
+            let entries = match scope.is_empty() {
+                true => &menu.submenus,
+                false => &menu.find_menu_by_id(scope)?.submenu,
+            };
+            let item = entries.iter().find(|item| {
+                item.is_activatable()
+                    && item
+                        .mnemonic()
+                        .is_some_and(|m| m.to_lowercase().eq(key.to_lowercase()))
+            })?;
+
+            let mut ids = scope.to_vec();
+            ids.push(item.id);
+            (ids, item.is_submenu())
+        };
+
+        self.get_focused_tree_state_mut()?.select(ids.clone());
+        if !is_submenu {
+            self.activate_menu_item(&ids).await;
+            return None;
+        }
+
+        let opened = self.get_focused_tree_state()?.opened().contains(&ids);
+        if !opened {
+            self.activate_menu_item(&ids).await;
+        }
+
+        Some(ids)
+    }
+
+    /// Activates inside the focused card: the selected menu entry for items with a menu,
+    /// the context menu for items without one.
+    pub async fn activate(&self) -> Option<()> {
+        let sni_key = self.get_focused_sni_key()?;
+        let action = self.get_items()?.get(sni_key)?.menu_action();
+
+        match action {
+            ItemAction::Menu => {
+                let ids = self.get_focused_tree_state()?.selected().to_vec();
+                if ids.is_empty() {
+                    self.get_focused_tree_state_mut()?.select_first();
+                    return Some(());
+                }
+                self.activate_menu_item(&ids).await
+            }
+            _ => {
+                self.activate_item(sni_key, ItemActivation::ContextMenu)
+                    .await
+            }
+        }
+    }
+
+    /// Activates the tray item itself, the same way clicking its icon in a graphical tray would.
+    pub async fn activate_item(&self, sni_key: &str, activation: ItemActivation) -> Option<()> {
+        log::debug!("Activating item with key: {}", sni_key);
+        let action = match activation {
+            ItemActivation::Primary => self.get_items()?.get(sni_key)?.primary_action(),
+            ItemActivation::ContextMenu => ItemAction::ContextMenu,
+            ItemActivation::Secondary => {
+                let activate_request = ActivateRequest::Secondary {
                     address: sni_key.to_string(),
-                    menu_path: path.to_string(),
-                    submenu_id: item.id,
+                    x: 0,
+                    y: 0,
                 };
                 let res = self.client.activate(activate_request).await;
-                log::debug!("Result of activating an item: {:?}", res);
+                log::debug!("Result of secondary activation: {:?}", res);
+                return Some(());
+            }
+        };
 
-                let _ = self
-                    .client
-                    .about_to_show_menuitem(sni_key.to_string(), path.to_string(), 0)
-                    .await;
+        match action {
+            ItemAction::Menu => {
+                // The menu is always shown in the card, so opening it means moving into it
+                let mut tree_state = self.sni_states.get(sni_key)?.tree_state.borrow_mut();
+                if tree_state.selected().is_empty() {
+                    tree_state.select_first();
+                }
+            }
+            ItemAction::ContextMenu => {
+                let res = dbus::context_menu(&self.connection, sni_key, 0, 0).await;
+                log::debug!("Result of requesting context menu: {:?}", res);
+            }
+            ItemAction::Activate => {
+                let activate_request = ActivateRequest::Default {
+                    address: sni_key.to_string(),
+                    x: 0,
+                    y: 0,
+                };
+                let res = self.client.activate(activate_request).await;
+                log::debug!("Result of activating an item: {:?}", res);
             }
-        } else {
-            tree_state.toggle(ids.to_vec());
         }
 
         Some(())
     }
+
+    /// Forwards a scroll to the tray item, e.g. to change the volume of a sound applet.
+    pub async fn scroll_item(
+        &self,
+        sni_key: &str,
+        delta: i32,
+        orientation: ScrollOrientation,
+    ) -> Option<()> {
+        log::debug!("Scrolling item with key: {} by {}", sni_key, delta);
+        let res = dbus::scroll(&self.connection, sni_key, delta, orientation).await;
+        log::debug!("Result of scrolling an item: {:?}", res);
+
+        Some(())
+    }
 }
+
+/// Input mode deciding how key presses are interpreted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Keys are mapped through the configured key map.
+    #[default]
+    Normal,
+    /// The next key is the access key of an entry in the submenu at the given path.
+    Mnemonic(Vec<Id>),
+    /// The inspector overlay is shown.
+    Inspector(Inspector),
+    /// Keys are typed into the search prompt.
+    Search(Search),
+    /// Keys are typed into the command palette.
+    Palette(Palette),
+    /// The key bindings are shown, scrolled by the given number of lines.
+    Help(u16),
+}
+
+impl Mode {
+    /// Whether an overlay covers the cards, so icon images must not be drawn.
+    pub fn is_overlay(&self) -> bool {
+        matches!(
+            self,
+            Mode::Inspector(_) | Mode::Search(_) | Mode::Palette(_) | Mode::Help(_)
+        )
+    }
+}
+
+/// Moves the selection of a rendered menu tree, skipping separators and wrapping
+/// around at both ends.
+fn move_selection(tree_state: &mut TreeState<Id>, menu: &TrayMenu, direction: FocusDirection) {
+    let start = tree_state.selected().to_vec();
+    let mut first_separator: Option<Vec<Id>> = None;
+    loop {
+        let moved = match direction {
+            FocusDirection::Up => tree_state.key_up(),
+            _ => tree_state.key_down(),
+        };
+        if !moved {
+            match direction {
+                FocusDirection::Up => tree_state.select_last(),
+                _ => tree_state.select_first(),
+            };
+        }
+
+        let selected = tree_state.selected().to_vec();
+        // Stop once we have gone around the whole menu
+        if selected == start || first_separator.as_ref() == Some(&selected) {
+            break;
+        }
+        match menu.find_menu_by_id(&selected) {
+            Some(item) if item.is_separator() => {
+                first_separator.get_or_insert(selected);
+            }
+            _ => break,
+        }
+    }
+}
+
 pub enum FocusDirection {
     Down,
     Up,
     Right,
     Left,
 }
+
+/// Kind of activation sent to a tray item.
+pub enum ItemActivation {
+    /// Equivalent of left-clicking the tray icon.
+    Primary,
+    /// Equivalent of middle-clicking the tray icon.
+    Secondary,
+    /// Equivalent of right-clicking the tray icon.
+    ContextMenu,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, menu, separator};
+    use ratatui::{buffer::Buffer, widgets::StatefulWidget};
+    use tui_tree_widget::{Tree, TreeItem};
+
+    /// Renders the menu once, so the state knows which entries are shown.
+    fn rendered(menu: &TrayMenu, selected: Id) -> TreeState<Id> {
+        let items: Vec<TreeItem<Id>> = menu
+            .submenus
+            .iter()
+            .map(|item| TreeItem::new_leaf(item.id, item.label.clone().unwrap_or_default()))
+            .collect();
+        let area = Rect::new(0, 0, 20, 10);
+        let mut state = TreeState::default();
+        state.select(vec![selected]);
+        StatefulWidget::render(
+            Tree::new(&items).unwrap(),
+            area,
+            &mut Buffer::empty(area),
+            &mut state,
+        );
+        state
+    }
+
+    #[test]
+    fn selection_skips_separators() {
+        let menu = menu(vec![entry(1, "Open"), separator(2), entry(3, "Quit")]);
+        let mut state = rendered(&menu, 1);
+        move_selection(&mut state, &menu, FocusDirection::Down);
+        assert_eq!(state.selected(), [3]);
+        move_selection(&mut state, &menu, FocusDirection::Up);
+        assert_eq!(state.selected(), [1]);
+    }
+
+    #[test]
+    fn selection_wraps_around_past_separators() {
+        let menu = menu(vec![
+            separator(1),
+            entry(2, "Open"),
+            entry(3, "Quit"),
+            separator(4),
+        ]);
+        let mut state = rendered(&menu, 3);
+        move_selection(&mut state, &menu, FocusDirection::Down);
+        assert_eq!(state.selected(), [2]);
+        move_selection(&mut state, &menu, FocusDirection::Up);
+        assert_eq!(state.selected(), [3]);
+    }
+
+    #[test]
+    fn selection_stops_in_a_menu_of_separators() {
+        let menu = menu(vec![separator(1), separator(2)]);
+        let mut state = rendered(&menu, 1);
+        move_selection(&mut state, &menu, FocusDirection::Down);
+        assert_eq!(state.selected(), [1]);
+    }
+}
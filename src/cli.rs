@@ -1,5 +1,5 @@
 use clap::value_parser;
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 
 #[derive(Parser, Debug)]
@@ -16,4 +16,89 @@ pub struct Cli {
     /// Generates completion scripts for the specified shell
     #[arg(long, value_name = "SHELL", value_enum)]
     pub completions: Option<Shell>,
+
+    /// Runs without the TUI
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Prints every tray item with its menu tree
+    #[command(alias = "list")]
+    Dump {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = DumpFormat::Json)]
+        format: DumpFormat,
+    },
+    /// Activates a menu entry by its label path, or the item itself
+    Activate {
+        /// Item id or title followed by menu labels, like 'nm-applet/VPN Connections/work'.
+        /// Labels are matched without access key underscores, ignoring case, and can
+        /// use the globs * and ?, with \* and \? for a literal * or ?.
+        /// Parts are separated by '/' or by ' › ' when the path contains it.
+        #[arg(required_unless_present_any = ["primary", "secondary"])]
+        path: Option<String>,
+
+        /// Item id or title, the path then holds only the menu labels
+        #[arg(short, long, required_if_eq_any = [("primary", "true"), ("secondary", "true")])]
+        item: Option<String>,
+
+        /// Activates the item itself, like clicking its icon
+        #[arg(long, conflicts_with_all = ["path", "secondary"])]
+        primary: bool,
+
+        /// Secondary activation of the item itself, like middle-clicking its icon
+        #[arg(long, conflicts_with = "path")]
+        secondary: bool,
+
+        /// Matches the item and the labels literally, without globs and minding case
+        #[arg(long)]
+        exact: bool,
+    },
+    /// Prints every tray event as a line of JSON
+    Watch,
+    /// Prints a summary line of the tray for status bars on every change
+    Status {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = StatusFormat::Plain)]
+        format: StatusFormat,
+
+        /// Template overriding the one in the config, can use {count}, {attention}, {titles} and {glyphs}
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Prints the summary once and exits
+        #[arg(long)]
+        once: bool,
+    },
+    /// Prints every activatable menu entry as a line like 'App › Submenu › Entry'
+    Menu {
+        /// Reads a chosen line back on stdin and activates it, unless stdin is a terminal,
+        /// as in 'tray-tui menu --pipe | fzf | tray-tui menu --pipe'
+        #[arg(long)]
+        pipe: bool,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// YAML document
+    Yaml,
+    /// Indented tree for reading
+    Text,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFormat {
+    /// A line of text
+    Plain,
+    /// A line of text with the `#` of titles escaped for tmux
+    Tmux,
+    /// JSON for waybar custom modules
+    Waybar,
+    /// The i3bar protocol, also spoken by swaybar
+    I3bar,
 }
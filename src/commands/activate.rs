@@ -0,0 +1,246 @@
+use system_tray::client::{ActivateRequest, Client};
+
+use crate::{
+    app::AppResult,
+    commands::{connect, lock_items, settle},
+    dbus,
+    entries::{menu_entries, MenuEntry, PATH_SEPARATOR},
+    pattern::glob_match,
+    wrappers::{GetAction, GetTitle, ItemAction},
+};
+
+/// Activates the menu entry at the path, or the item itself with `primary` or `secondary`.
+pub async fn run(
+    path: Option<String>,
+    item: Option<String>,
+    primary: bool,
+    secondary: bool,
+    exact: bool,
+) -> AppResult<()> {
+    let (client, mut tray_rx) = connect().await?;
+
+    let mut labels = path.as_deref().map(split_path).unwrap_or_default();
+    let item = match item {
+        Some(item) => item,
+        None if labels.is_empty() => return Err("no item given".into()),
+        None => labels.remove(0),
+    };
+    let keys = matching_items(&client, &item, exact)?;
+
+    if primary || secondary {
+        let [key] = keys.as_slice() else {
+            return Err(
+                format!("'{item}' matches {} items: {}", keys.len(), keys.join(", ")).into(),
+            );
+        };
+        return activate_item(&client, key, secondary).await;
+    }
+
+    if labels.is_empty() {
+        return Err(format!("no menu labels after the item in '{item}'").into());
+    }
+
+    // Submenus along the path may only be populated once they are about to be shown
+    for depth in 1..labels.len() {
+        let mut shown = false;
+        for entry in matching_entries(&client, &keys, &labels[..depth], exact)? {
+            if entry.is_submenu && show_submenu(&client, &entry).await.is_ok() {
+                shown = true;
+            }
+        }
+        if shown {
+            settle(&mut tray_rx).await;
+        }
+    }
+
+    let path = labels.join(PATH_SEPARATOR);
+    let entries: Vec<MenuEntry> = matching_entries(&client, &keys, &labels, exact)?
+        .into_iter()
+        .filter(|entry| !entry.is_submenu)
+        .collect();
+    match entries.as_slice() {
+        [] => Err(format!("no menu entry of '{item}' matches '{path}'").into()),
+        [entry] => activate_entry(&client, entry).await,
+        entries => {
+            let paths: Vec<String> = entries
+                .iter()
+                .map(|entry| format!("  {}", entry.path()))
+                .collect();
+            Err(format!(
+                "'{path}' matches {} menu entries:\n{}",
+                entries.len(),
+                paths.join("\n")
+            )
+            .into())
+        }
+    }
+}
+
+/// Splits the path on ` › ` when it is used, on `/` otherwise.
+pub fn split_path(path: &str) -> Vec<String> {
+    let separator = match path.contains(PATH_SEPARATOR) {
+        true => PATH_SEPARATOR,
+        false => "/",
+    };
+    path.split(separator)
+        .map(|label| label.trim().to_owned())
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+/// Matches the text against the glob, or compares them with `exact`.
+fn label_match(pattern: &str, text: &str, exact: bool) -> bool {
+    match exact {
+        true => pattern == text,
+        false => glob_match(pattern, text),
+    }
+}
+
+/// Keys of the items whose id or title matches the pattern.
+fn matching_items(client: &Client, pattern: &str, exact: bool) -> AppResult<Vec<String>> {
+    let items = client.items();
+    let mut keys: Vec<String> = lock_items(&items)?
+        .iter()
+        .filter(|(_, (item, _))| {
+            label_match(pattern, &item.id, exact) || label_match(pattern, item.get_title(), exact)
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    keys.sort();
+
+    match keys.is_empty() {
+        true => Err(format!("no tray item matches '{pattern}'").into()),
+        false => Ok(keys),
+    }
+}
+
+/// Entries of the items whose labels below the item title match the patterns.
+///
+/// Goes through [menu_entries] rather than [crate::wrappers::FindMenuById], which follows
+/// known dbusmenu ids, while a path needs every entry with the labels leading to it.
+fn matching_entries(
+    client: &Client,
+    keys: &[String],
+    labels: &[String],
+    exact: bool,
+) -> AppResult<Vec<MenuEntry>> {
+    let items = client.items();
+    let items = lock_items(&items)?;
+    Ok(keys
+        .iter()
+        .filter_map(|key| Some(menu_entries(key, items.get(key)?)))
+        .flatten()
+        .filter(|entry| {
+            entry.labels.len() == labels.len() + 1
+                && labels
+                    .iter()
+                    .zip(&entry.labels[1..])
+                    .all(|(pattern, label)| label_match(pattern, label, exact))
+        })
+        .collect())
+}
+
+fn menu_path(client: &Client, key: &str) -> AppResult<String> {
+    let items = client.items();
+    let items = lock_items(&items)?;
+    items
+        .get(key)
+        .and_then(|(item, _)| item.menu.clone())
+        .ok_or_else(|| format!("item {key} has no menu").into())
+}
+
+pub async fn show_submenu(client: &Client, entry: &MenuEntry) -> AppResult<()> {
+    let menu_path = menu_path(client, &entry.key)?;
+    let id = *entry.ids.last().unwrap_or(&0);
+    client
+        .about_to_show_menuitem(entry.key.clone(), menu_path, id)
+        .await?;
+    Ok(())
+}
+
+pub async fn activate_entry(client: &Client, entry: &MenuEntry) -> AppResult<()> {
+    let activate_request = ActivateRequest::MenuItem {
+        address: entry.key.clone(),
+        menu_path: menu_path(client, &entry.key)?,
+        submenu_id: *entry.ids.last().unwrap_or(&0),
+    };
+    client.activate(activate_request).await?;
+    Ok(())
+}
+
+/// Activates the item itself, opening its context menu if it only has a menu.
+async fn activate_item(client: &Client, key: &str, secondary: bool) -> AppResult<()> {
+    let address = key.to_owned();
+    if secondary {
+        let activate_request = ActivateRequest::Secondary {
+            address,
+            x: 0,
+            y: 0,
+        };
+        client.activate(activate_request).await?;
+        return Ok(());
+    }
+
+    let action = {
+        let items = client.items();
+        let items = lock_items(&items)?;
+        items.get(key).map(|item| item.primary_action())
+    };
+    match action {
+        Some(ItemAction::Activate) => {
+            let activate_request = ActivateRequest::Default {
+                address,
+                x: 0,
+                y: 0,
+            };
+            client.activate(activate_request).await?;
+        }
+        // There is no card to show the menu in, so the item shows its own
+        Some(_) => {
+            let connection = zbus::Connection::session().await?;
+            dbus::context_menu(&connection, key, 0, 0).await?;
+        }
+        None => return Err(format!("item {key} went away").into()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_splits_on_slashes() {
+        assert_eq!(
+            split_path("nm-applet/VPN Connections/work"),
+            ["nm-applet", "VPN Connections", "work"]
+        );
+        assert_eq!(split_path("steam"), ["steam"]);
+    }
+
+    #[test]
+    fn path_splits_on_arrows_when_they_are_used() {
+        assert_eq!(
+            split_path("NetworkManager › VPN Connections › work/home"),
+            ["NetworkManager", "VPN Connections", "work/home"]
+        );
+    }
+
+    #[test]
+    fn path_parts_are_trimmed_and_empty_ones_dropped() {
+        assert_eq!(
+            split_path(" /nm-applet// VPN Connections /work/ "),
+            ["nm-applet", "VPN Connections", "work"]
+        );
+        assert!(split_path("").is_empty());
+        assert!(split_path(" › ").is_empty());
+    }
+
+    #[test]
+    fn exact_labels_mind_case_and_globs() {
+        assert!(label_match("VPN*", "vpn connections", false));
+        assert!(!label_match("VPN*", "VPN Connections", true));
+        assert!(label_match("VPN*", "VPN*", true));
+        assert!(!label_match("vpn*", "VPN*", true));
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::{
+    app::AppResult,
+    cli::DumpFormat,
+    commands::{
+        connect, lock_items,
+        snapshot::{ItemSnapshot, MenuSnapshot},
+    },
+};
+
+/// Prints every item of the tray with its menu tree.
+pub async fn run(format: DumpFormat) -> AppResult<()> {
+    let (client, _tray_rx) = connect().await?;
+    let items = client.items();
+    let mut snapshot: Vec<ItemSnapshot> = lock_items(&items)?
+        .iter()
+        .map(|(key, item)| ItemSnapshot::new(key, item))
+        .collect();
+    snapshot.sort_by(|a, b| a.key.cmp(&b.key));
+
+    let output = match format {
+        DumpFormat::Json => serde_json::to_string_pretty(&snapshot)? + "\n",
+        DumpFormat::Yaml => yaml(&serde_json::to_value(&snapshot)?),
+        DumpFormat::Text => text(&snapshot),
+    };
+    io::stdout().lock().write_all(output.as_bytes())?;
+    Ok(())
+}
+
+/// Emits the value as a YAML document.
+fn yaml(value: &Value) -> String {
+    let mut out = String::from("---\n");
+    match is_scalar(value) {
+        true => out.push_str(&(scalar(value) + "\n")),
+        false => yaml_block(value, 0, &mut out),
+    }
+    out
+}
+
+/// Values written on the line of their key, including empty collections.
+fn is_scalar(value: &Value) -> bool {
+    match value {
+        Value::Array(array) => array.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => true,
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("null"),
+        Value::Array(_) => String::from("[]"),
+        Value::Object(_) => String::from("{}"),
+        Value::String(text) => quote(text),
+        value => value.to_string(),
+    }
+}
+
+/// Double-quoted YAML scalar. JSON strings are almost that,
+/// but YAML doesn't allow DEL and most C1 control characters unescaped.
+fn quote(text: &str) -> String {
+    Value::from(text)
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '\u{7f}'..='\u{84}' | '\u{86}'..='\u{9f}' | '\u{fffe}' | '\u{ffff}' => {
+                format!("\\u{:04x}", c as u32)
+            }
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Key of a mapping, quoted unless it can't be read as anything but a plain string.
+fn yaml_key(key: &str) -> String {
+    let plain = key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(
+            key.to_lowercase().as_str(),
+            "y" | "n" | "yes" | "no" | "on" | "off" | "true" | "false" | "null"
+        );
+    match plain {
+        true => key.to_owned(),
+        false => quote(key),
+    }
+}
+
+fn yaml_block(value: &Value, indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                out.push_str(&format!("{pad}{}:", yaml_key(key)));
+                match is_scalar(value) {
+                    true => out.push_str(&format!(" {}\n", scalar(value))),
+                    false => {
+                        out.push('\n');
+                        yaml_block(value, indent + 2, out);
+                    }
+                }
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                match is_scalar(value) {
+                    true => out.push_str(&format!("{pad}- {}\n", scalar(value))),
+                    false => {
+                        // The first line of the nested block goes right after the dash
+                        let mut block = String::new();
+                        yaml_block(value, indent + 2, &mut block);
+                        out.push_str(&format!("{pad}- {}", &block[indent + 2..]));
+                    }
+                }
+            }
+        }
+        value => out.push_str(&format!("{pad}{}\n", scalar(value))),
+    }
+}
+
+/// Indented tree meant for reading.
+fn text(snapshot: &[ItemSnapshot]) -> String {
+    let mut out = String::new();
+    for item in snapshot {
+        out.push_str(&format!(
+            "{} [{}] {} {}\n",
+            item.title, item.status, item.id, item.key
+        ));
+        if let Some(tooltip) = &item.tooltip {
+            out.push_str(&format!("  tooltip: {}", tooltip.title));
+            if !tooltip.description.is_empty() {
+                out.push_str(&format!(" — {}", tooltip.description.replace('\n', " ")));
+            }
+            out.push('\n');
+        }
+        match &item.menu {
+            Some(menu) => {
+                out.push_str("  menu:\n");
+                text_menu(menu, 2, &mut out);
+            }
+            None => out.push_str("  menu: -\n"),
+        }
+    }
+    out
+}
+
+fn text_menu(items: &[MenuSnapshot], depth: usize, out: &mut String) {
+    for item in items {
+        let pad = "  ".repeat(depth);
+        let Some(label) = &item.label else {
+            out.push_str(&format!("{pad}────\n"));
+            continue;
+        };
+        out.push_str(&format!("{pad}{label}"));
+        if let (Some(toggle), Some(toggled)) = (item.toggle, item.toggled) {
+            let state = if toggled { "on" } else { "off" };
+            out.push_str(&format!(" [{toggle} {state}]"));
+        }
+        if !item.enabled {
+            out.push_str(" (disabled)");
+        }
+        if !item.visible {
+            out.push_str(" (hidden)");
+        }
+        out.push('\n');
+        text_menu(&item.children, depth + 1, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use yaml_rust2::{Yaml, YamlLoader};
+
+    use super::*;
+
+    /// Reads the YAML back with a real parser, as JSON to compare it with.
+    fn parse(yaml: &str) -> Value {
+        fn to_json(yaml: Yaml) -> Value {
+            match yaml {
+                Yaml::Null => Value::Null,
+                Yaml::Boolean(b) => Value::from(b),
+                Yaml::Integer(i) => Value::from(i),
+                Yaml::Real(real) => Value::from(real.parse::<f64>().unwrap()),
+                Yaml::String(text) => Value::from(text),
+                Yaml::Array(array) => array.into_iter().map(to_json).collect(),
+                Yaml::Hash(hash) => Value::Object(
+                    hash.into_iter()
+                        .map(|(key, value)| (key.into_string().unwrap(), to_json(value)))
+                        .collect(),
+                ),
+                yaml => panic!("unexpected {yaml:?}"),
+            }
+        }
+        let mut documents = YamlLoader::load_from_str(yaml).unwrap();
+        assert_eq!(documents.len(), 1);
+        to_json(documents.remove(0))
+    }
+
+    #[test]
+    fn yaml_scalars() {
+        assert_eq!(yaml(&json!(null)), "---\nnull\n");
+        assert_eq!(yaml(&json!("a: \"b\"\n")), "---\n\"a: \\\"b\\\"\\n\"\n");
+        assert_eq!(yaml(&json!([])), "---\n[]\n");
+        assert_eq!(yaml(&json!({})), "---\n{}\n");
+    }
+
+    #[test]
+    fn yaml_objects_nest_by_indent() {
+        let value = json!({"id": "nm-applet", "tooltip": {"title": "Wi-Fi", "icon": null}});
+        assert_eq!(
+            yaml(&value),
+            "---\nid: \"nm-applet\"\ntooltip:\n  icon: null\n  title: \"Wi-Fi\"\n"
+        );
+    }
+
+    #[test]
+    fn yaml_empty_collections_stay_on_the_key_line() {
+        let value = json!({"children": [], "props": {}, "items": [[], {}]});
+        assert_eq!(
+            yaml(&value),
+            "---\nchildren: []\nitems:\n  - []\n  - {}\nprops: {}\n"
+        );
+    }
+
+    /// The document with the given lines, to keep the indentation readable.
+    fn document(lines: &[&str]) -> String {
+        format!("---\n{}\n", lines.join("\n"))
+    }
+
+    #[test]
+    fn yaml_blocks_in_arrays_start_after_the_dash() {
+        let value = json!([{"label": "VPN", "children": [{"label": "work", "children": []}]}]);
+        assert_eq!(
+            yaml(&value),
+            document(&[
+                "- children:",
+                "    - children: []",
+                "      label: \"work\"",
+                "  label: \"VPN\"",
+            ])
+        );
+    }
+
+    #[test]
+    fn yaml_nested_arrays() {
+        let value = json!({"shortcut": [["Control", "q"], ["Alt", [1, 2]]]});
+        assert_eq!(
+            yaml(&value),
+            document(&[
+                "shortcut:",
+                "  - - \"Control\"",
+                "    - \"q\"",
+                "  - - \"Alt\"",
+                "    - - 1",
+                "      - 2",
+            ])
+        );
+    }
+
+    #[test]
+    fn yaml_strings_that_need_quoting_read_back() {
+        let value = json!({
+            "title": "key: value # not a comment",
+            "dash": "- not a list",
+            "words": ["yes", "no", "true", "null", "~", "", "123", "0x1f", "@at", "`tick`"],
+            "tooltip": "Battery\n\t80% \"charging\"\r\n\\ done",
+            "controls": "\u{0}\u{7}\u{1b}\u{7f}\u{85}\u{9b}\u{fffe}",
+            "unicode": "NetworkManager › VPN ✓ 🔋",
+            "numbers": [0, -1, 2.5, true, false, null],
+        });
+        assert_eq!(parse(&yaml(&value)), value);
+    }
+
+    #[test]
+    fn yaml_keys_that_need_quoting_read_back() {
+        let value = json!({
+            "plain_key1": 1,
+            "with: colon": 2,
+            "# hash": 3,
+            "- dash": 4,
+            "yes": 5,
+            "No": 6,
+            "": 7,
+            "1st": 8,
+            "multi\nline": {"nested key": [{"a b": []}]},
+        });
+        let yaml = yaml(&value);
+        assert!(yaml.contains("\nplain_key1: 1\n"));
+        assert!(yaml.contains("\n\"yes\": 5\n"));
+        assert_eq!(parse(&yaml), value);
+    }
+
+    #[test]
+    fn yaml_dump_of_nested_values_reads_back() {
+        let value = json!([
+            {"label": "VPN", "children": [{"label": "work", "children": [], "shortcut": [["Control", "w"]]}]},
+            [[1, [2, []]], {}],
+            "last",
+        ]);
+        assert_eq!(parse(&yaml(&value)), value);
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashSet,
+    io::{self, IsTerminal},
+};
+
+use system_tray::client::{Client, Event};
+use tokio::sync::broadcast::Receiver;
+
+use crate::{
+    app::AppResult,
+    commands::{
+        activate::{activate_entry, show_submenu},
+        connect, lock_items, settle, write_stdout,
+    },
+    entries::{menu_entries, MenuEntry},
+    wrappers::{GetTitle, Id},
+};
+
+/// Prints every activatable leaf of every menu. With `pipe` and a piped stdin,
+/// activates the leaf read from stdin instead, so it can start and end a pipeline.
+pub async fn run(pipe: bool) -> AppResult<()> {
+    let (client, mut tray_rx) = connect().await?;
+    load_submenus(&client, &mut tray_rx).await?;
+    let entries = leaves(&client)?;
+
+    if !pipe || io::stdin().is_terminal() {
+        let lines: String = entries.iter().map(|entry| entry.path() + "\n").collect();
+        write_stdout(&lines)?;
+        return Ok(());
+    }
+
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    match choose(&entries, &line)? {
+        Some(entry) => activate_entry(&client, entry).await,
+        None => Ok(()),
+    }
+}
+
+/// Entry whose path is the line read back from the launcher.
+fn choose<'a>(entries: &'a [MenuEntry], line: &str) -> AppResult<Option<&'a MenuEntry>> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    // Nothing was chosen, e.g. the launcher was cancelled
+    if line.is_empty() {
+        return Ok(None);
+    }
+
+    let chosen: Vec<&MenuEntry> = entries
+        .iter()
+        .filter(|entry| entry.path() == line)
+        .collect();
+    match chosen.as_slice() {
+        [] => Err(format!("no menu entry '{line}'").into()),
+        [entry] => Ok(Some(entry)),
+        chosen => Err(format!("'{line}' matches {} menu entries", chosen.len()).into()),
+    }
+}
+
+/// Entries that aren't submenus, items ordered by title.
+fn leaves(client: &Client) -> AppResult<Vec<MenuEntry>> {
+    let items = client.items();
+    let items = lock_items(&items)?;
+    let mut items: Vec<_> = items.iter().collect();
+    items.sort_by_key(|(key, (item, _))| (item.get_title(), *key));
+
+    Ok(items
+        .into_iter()
+        .flat_map(|(key, item)| menu_entries(key, item))
+        .filter(|entry| !entry.is_submenu)
+        .collect())
+}
+
+/// Asks every submenu to be shown once, level by level,
+/// since some apps only populate their submenus then.
+async fn load_submenus(client: &Client, tray_rx: &mut Receiver<Event>) -> AppResult<()> {
+    let mut shown: HashSet<(String, Id)> = HashSet::new();
+    loop {
+        let submenus: Vec<MenuEntry> = {
+            let items = client.items();
+            let items = lock_items(&items)?;
+            items
+                .iter()
+                .flat_map(|(key, item)| menu_entries(key, item))
+                .filter(|entry| entry.is_submenu)
+                .filter(|entry| {
+                    let id = *entry.ids.last().unwrap_or(&0);
+                    shown.insert((entry.key.clone(), id))
+                })
+                .collect()
+        };
+        if submenus.is_empty() {
+            return Ok(());
+        }
+
+        for entry in &submenus {
+            if let Err(e) = show_submenu(client, entry).await {
+                log::debug!("Failed to show submenu of {}: {}", entry.key, e);
+            }
+        }
+        settle(tray_rx).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(id: Id, labels: &[&str]) -> MenuEntry {
+        MenuEntry {
+            key: String::from(":1.42"),
+            ids: vec![id],
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            is_submenu: false,
+        }
+    }
+
+    #[test]
+    fn chosen_line_picks_the_entry_by_path() {
+        let entries = [leaf(1, &["Steam", "Library"]), leaf(2, &["Steam", "Exit"])];
+        let chosen = choose(&entries, "Steam › Exit\n").unwrap();
+        assert_eq!(chosen.map(|entry| entry.ids.clone()), Some(vec![2]));
+    }
+
+    #[test]
+    fn empty_line_chooses_nothing() {
+        let entries = [leaf(1, &["Steam", "Exit"])];
+        assert!(choose(&entries, "\n").unwrap().is_none());
+        assert!(choose(&entries, "").unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_or_ambiguous_lines_are_errors() {
+        let entries = [leaf(1, &["Steam", "Exit"]), leaf(2, &["Steam", "Exit"])];
+        assert!(choose(&entries[..1], "Steam › Quit").is_err());
+        let error = choose(&entries, "Steam › Exit").unwrap_err();
+        assert_eq!(error.to_string(), "'Steam › Exit' matches 2 menu entries");
+    }
+}
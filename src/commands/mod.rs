@@ -0,0 +1,103 @@
+//! Subcommands working with the tray without the TUI.
+
+use std::{
+    io::{self, ErrorKind, Write},
+    sync::{Mutex, MutexGuard},
+    time::Duration,
+};
+
+use system_tray::{
+    client::{Client, Event},
+    data::BaseMap,
+};
+use tokio::{
+    sync::broadcast::{error::RecvError, Receiver},
+    time::timeout,
+};
+
+use crate::{app::AppResult, cli::Commands, config::Config};
+
+pub mod activate;
+pub mod dump;
+pub mod menu;
+pub mod snapshot;
+pub mod status;
+pub mod watch;
+
+/// Time without tray events after which the initial items are considered loaded.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Longest time to wait for the initial items.
+const MAX_WAIT: Duration = Duration::from_secs(5);
+
+pub async fn run(command: Commands, config: Config) -> AppResult<()> {
+    match command {
+        Commands::Dump { format } => dump::run(format).await,
+        Commands::Activate {
+            path,
+            item,
+            primary,
+            secondary,
+            exact,
+        } => activate::run(path, item, primary, secondary, exact).await,
+        Commands::Watch => watch::run().await,
+        Commands::Status {
+            format,
+            template,
+            once,
+        } => status::run(format, template, once, config).await,
+        Commands::Menu { pipe } => menu::run(pipe).await,
+    }
+}
+
+/// Connects to the tray and waits until the items registered so far and their menus are loaded.
+pub async fn connect() -> AppResult<(Client, Receiver<Event>)> {
+    let client = Client::new().await?;
+    let mut tray_rx = client.subscribe();
+    settle(&mut tray_rx).await;
+
+    Ok((client, tray_rx))
+}
+
+/// Waits until the tray events stop coming, e.g. after asking for a submenu to be loaded.
+pub async fn settle(tray_rx: &mut Receiver<Event>) {
+    let _ = timeout(MAX_WAIT, async {
+        // Missed events are still events, only a quiet channel means the tray settled
+        while let Ok(res) = timeout(SETTLE_TIME, tray_rx.recv()).await {
+            match res {
+                Ok(event) => log::debug!("Event for {}", event_key(&event)),
+                Err(RecvError::Lagged(skipped)) => log::debug!("Skipped {} events", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+    .await;
+}
+
+/// Locks the items of the client, with an error rather than a panic if the lock is poisoned.
+pub fn lock_items(items: &Mutex<BaseMap>) -> AppResult<MutexGuard<'_, BaseMap>> {
+    items
+        .lock()
+        .map_err(|_| "the tray items lock is poisoned".into())
+}
+
+/// Key of the item the event is about.
+pub fn event_key(event: &Event) -> &str {
+    match event {
+        Event::Add(key, _) | Event::Update(key, _) | Event::Remove(key) => key,
+    }
+}
+
+/// Writes the text to stdout right away.
+/// Returns `false` once the reader is gone, e.g. with `tray-tui watch | head`.
+pub fn write_stdout(text: &str) -> io::Result<bool> {
+    let mut stdout = io::stdout().lock();
+    match stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+    {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => Ok(false),
+        Err(e) => Err(e),
+    }
+}
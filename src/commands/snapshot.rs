@@ -0,0 +1,333 @@
+use serde::Serialize;
+use system_tray::{
+    client::{Event, UpdateEvent},
+    item::{StatusNotifierItem, Tooltip},
+    menu::{MenuDiff, MenuItem, MenuType, ToggleState, ToggleType, TrayMenu},
+};
+
+use crate::wrappers::{parse_mnemonic, GetTitle, Id};
+
+/// Serializable state of a tray item. Enums are named as on D-Bus.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemSnapshot {
+    pub key: String,
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    pub category: String,
+    pub icon_name: Option<String>,
+    pub tooltip: Option<TooltipSnapshot>,
+    pub item_is_menu: bool,
+    /// `None` until the menu is loaded
+    pub menu: Option<Vec<MenuSnapshot>>,
+}
+
+impl ItemSnapshot {
+    pub fn new(key: &str, (item, menu): &(StatusNotifierItem, Option<TrayMenu>)) -> Self {
+        Self {
+            key: key.to_owned(),
+            id: item.id.clone(),
+            title: item.get_title().clone(),
+            status: format!("{:?}", item.status),
+            category: format!("{:?}", item.category),
+            icon_name: item.icon_name.clone().filter(|name| !name.is_empty()),
+            tooltip: item.tool_tip.as_ref().map(TooltipSnapshot::from),
+            item_is_menu: item.item_is_menu,
+            menu: menu.as_ref().map(|menu| MenuSnapshot::tree(&menu.submenus)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TooltipSnapshot {
+    pub title: String,
+    pub description: String,
+    pub icon_name: String,
+}
+
+impl From<&Tooltip> for TooltipSnapshot {
+    fn from(tooltip: &Tooltip) -> Self {
+        Self {
+            title: tooltip.title.clone(),
+            description: tooltip.description.clone(),
+            icon_name: tooltip.icon_name.clone(),
+        }
+    }
+}
+
+/// Serializable menu entry with its children.
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuSnapshot {
+    pub id: Id,
+    /// label without access key underscores, `None` for separators
+    pub label: Option<String>,
+    pub enabled: bool,
+    pub visible: bool,
+    /// `checkmark` or `radio` for toggleable entries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toggle: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toggled: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<MenuSnapshot>,
+}
+
+impl MenuSnapshot {
+    pub fn tree(items: &[MenuItem]) -> Vec<MenuSnapshot> {
+        items.iter().map(MenuSnapshot::from).collect()
+    }
+}
+
+impl From<&MenuItem> for MenuSnapshot {
+    fn from(item: &MenuItem) -> Self {
+        let label = match item.menu_type {
+            MenuType::Separator => None,
+            MenuType::Standard => Some(parse_mnemonic(item.label.as_deref().unwrap_or_default()).0),
+        };
+        let toggle = match item.toggle_type {
+            ToggleType::Checkmark => Some("checkmark"),
+            ToggleType::Radio => Some("radio"),
+            ToggleType::CannotBeToggled => None,
+        };
+        Self {
+            id: item.id,
+            label,
+            enabled: item.enabled,
+            visible: item.visible,
+            toggle,
+            toggled: toggle.map(|_| item.toggle_state == ToggleState::On),
+            children: MenuSnapshot::tree(&item.submenu),
+        }
+    }
+}
+
+/// Serializable tray event, named after the [UpdateEvent] variants.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSnapshot {
+    pub key: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// What changed about the item, with the new values.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Change {
+    Add {
+        item: ItemSnapshot,
+    },
+    Remove,
+    Title {
+        title: Option<String>,
+    },
+    Status {
+        status: String,
+    },
+    Tooltip {
+        tooltip: Option<TooltipSnapshot>,
+    },
+    Icon {
+        icon_name: Option<String>,
+        /// sizes of the pixmaps like `22x22`
+        icon_pixmaps: Vec<String>,
+    },
+    AttentionIcon {
+        icon_name: Option<String>,
+    },
+    OverlayIcon {
+        icon_name: Option<String>,
+    },
+    /// The whole menu layout was sent
+    Menu {
+        menu: Vec<MenuSnapshot>,
+    },
+    /// Properties of some menu entries changed
+    MenuDiff {
+        diff: Vec<MenuDiffSnapshot>,
+    },
+    MenuConnect {
+        menu: String,
+    },
+}
+
+impl From<&Event> for EventSnapshot {
+    fn from(event: &Event) -> Self {
+        let (key, change) = match event {
+            Event::Add(key, item) => {
+                // Items are added before their menu is loaded
+                let item = ItemSnapshot::new(key, &(*item.clone(), None));
+                (key, Change::Add { item })
+            }
+            Event::Remove(key) => (key, Change::Remove),
+            Event::Update(key, update) => (key, Change::from(update)),
+        };
+        Self {
+            key: key.clone(),
+            change,
+        }
+    }
+}
+
+impl From<&UpdateEvent> for Change {
+    fn from(update: &UpdateEvent) -> Self {
+        match update {
+            UpdateEvent::Title(title) => Change::Title {
+                title: title.clone(),
+            },
+            UpdateEvent::Status(status) => Change::Status {
+                status: format!("{status:?}"),
+            },
+            UpdateEvent::Tooltip(tooltip) => Change::Tooltip {
+                tooltip: tooltip.as_ref().map(TooltipSnapshot::from),
+            },
+            UpdateEvent::Icon {
+                icon_name,
+                icon_pixmap,
+            } => Change::Icon {
+                icon_name: icon_name.clone(),
+                icon_pixmaps: icon_pixmap
+                    .iter()
+                    .flatten()
+                    .map(|p| format!("{}x{}", p.width, p.height))
+                    .collect(),
+            },
+            UpdateEvent::AttentionIcon(icon_name) => Change::AttentionIcon {
+                icon_name: icon_name.clone(),
+            },
+            UpdateEvent::OverlayIcon(icon_name) => Change::OverlayIcon {
+                icon_name: icon_name.clone(),
+            },
+            UpdateEvent::Menu(menu) => Change::Menu {
+                menu: MenuSnapshot::tree(&menu.submenus),
+            },
+            UpdateEvent::MenuDiff(diff) => Change::MenuDiff {
+                diff: diff.iter().map(MenuDiffSnapshot::from).collect(),
+            },
+            UpdateEvent::MenuConnect(menu) => Change::MenuConnect { menu: menu.clone() },
+        }
+    }
+}
+
+/// Changed properties of a menu entry, unchanged ones are left out.
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuDiffSnapshot {
+    pub id: Id,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_name: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toggled: Option<bool>,
+    /// properties reset to their defaults
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+}
+
+impl From<&MenuDiff> for MenuDiffSnapshot {
+    fn from(diff: &MenuDiff) -> Self {
+        let update = &diff.update;
+        Self {
+            id: diff.id,
+            label: update
+                .label
+                .as_ref()
+                .map(|label| label.as_deref().map(|label| parse_mnemonic(label).0)),
+            enabled: update.enabled,
+            visible: update.visible,
+            icon_name: update.icon_name.clone(),
+            toggled: update.toggle_state.map(|state| state == ToggleState::On),
+            remove: diff.remove.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, item, separator, submenu};
+    use serde_json::{json, Value};
+    use system_tray::{item::Status, menu::MenuItemUpdate};
+
+    fn to_json(event: &Event) -> Value {
+        serde_json::to_value(EventSnapshot::from(event)).unwrap()
+    }
+
+    #[test]
+    fn added_items_have_no_menu_yet() {
+        let item = StatusNotifierItem {
+            title: Some(String::from("Steam")),
+            ..item("steam")
+        };
+        let event = to_json(&Event::Add(String::from(":1.42"), Box::new(item)));
+        assert_eq!(event["event"], "add");
+        assert_eq!(event["key"], ":1.42");
+        assert_eq!(event["item"]["title"], "Steam");
+        assert_eq!(event["item"]["status"], "Active");
+        assert_eq!(event["item"]["menu"], Value::Null);
+    }
+
+    #[test]
+    fn updates_are_named_after_the_property() {
+        let key = String::from(":1.42");
+        let status = UpdateEvent::Status(Status::NeedsAttention);
+        assert_eq!(
+            to_json(&Event::Update(key.clone(), status)),
+            json!({"key": ":1.42", "event": "status", "status": "NeedsAttention"})
+        );
+        assert_eq!(
+            to_json(&Event::Remove(key)),
+            json!({"key": ":1.42", "event": "remove"})
+        );
+    }
+
+    #[test]
+    fn menus_are_nested_without_access_keys() {
+        let mute = MenuItem {
+            toggle_type: ToggleType::Checkmark,
+            toggle_state: ToggleState::On,
+            ..entry(3, "_Mute")
+        };
+        let items = [submenu(1, "_Output", vec![mute]), separator(2)];
+        assert_eq!(
+            serde_json::to_value(MenuSnapshot::tree(&items)).unwrap(),
+            json!([
+                {
+                    "id": 1,
+                    "label": "Output",
+                    "enabled": true,
+                    "visible": true,
+                    "children": [{
+                        "id": 3,
+                        "label": "Mute",
+                        "enabled": true,
+                        "visible": true,
+                        "toggle": "checkmark",
+                        "toggled": true,
+                    }],
+                },
+                {"id": 2, "label": null, "enabled": true, "visible": true},
+            ])
+        );
+    }
+
+    #[test]
+    fn menu_diffs_leave_out_unchanged_properties() {
+        let diff = MenuDiff {
+            id: 3,
+            update: MenuItemUpdate {
+                label: Some(Some(String::from("_Unmute"))),
+                enabled: Some(false),
+                ..MenuItemUpdate::default()
+            },
+            remove: vec![String::from("icon-name")],
+        };
+        assert_eq!(
+            serde_json::to_value(MenuDiffSnapshot::from(&diff)).unwrap(),
+            json!({"id": 3, "label": "Unmute", "enabled": false, "remove": ["icon-name"]})
+        );
+    }
+}
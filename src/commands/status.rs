@@ -0,0 +1,247 @@
+use serde_json::json;
+use system_tray::{
+    client::Client,
+    item::{Status, StatusNotifierItem},
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    app::AppResult,
+    cli::StatusFormat,
+    commands::{connect, lock_items, write_stdout},
+    config::{Config, PassiveItems},
+    wrappers::{GetGlyph, GetTitle},
+};
+
+/// Summary of the tray the templates are rendered from.
+struct Summary {
+    count: usize,
+    attention: usize,
+    titles: Vec<String>,
+    /// glyph of every item, or its title without one
+    glyphs: Vec<String>,
+}
+
+impl Summary {
+    /// Items in the order of the cards, following the sorting and passive items options.
+    fn new(client: &Client, config: &Config) -> AppResult<Self> {
+        let items = client.items();
+        let items = lock_items(&items)?;
+        let mut items: Vec<_> = items
+            .iter()
+            .map(|(key, (item, _))| (key, item))
+            .filter(|(_, item)| {
+                config.passive_items != PassiveItems::Hide || item.status != Status::Passive
+            })
+            .collect();
+        items.sort_by_key(|(key, _)| *key);
+        if config.sorting {
+            items.sort_by_key(|(_, item)| item.get_title());
+        }
+        if config.passive_items == PassiveItems::End {
+            items.sort_by_key(|(_, item)| item.status == Status::Passive);
+        }
+
+        let glyph = |item: &StatusNotifierItem| {
+            config
+                .nerd_font
+                .then(|| item.get_glyph(&config.glyphs))
+                .flatten()
+                .unwrap_or(item.get_title())
+                .to_owned()
+        };
+        Ok(Self {
+            count: items.len(),
+            attention: items
+                .iter()
+                .filter(|(_, item)| item.status == Status::NeedsAttention)
+                .count(),
+            titles: items
+                .iter()
+                .map(|(_, item)| item.get_title().clone())
+                .collect(),
+            glyphs: items.iter().map(|(_, item)| glyph(item)).collect(),
+        })
+    }
+
+    /// Fills in the template, passing the titles and glyphs through `escape`.
+    fn render(&self, template: &str, escape: fn(&str) -> String) -> String {
+        let titles: Vec<String> = self.titles.iter().map(|t| escape(t)).collect();
+        let glyphs: Vec<String> = self.glyphs.iter().map(|g| escape(g)).collect();
+        template
+            .replace("{count}", &self.count.to_string())
+            .replace("{attention}", &self.attention.to_string())
+            .replace("{titles}", &titles.join(", "))
+            .replace("{glyphs}", &glyphs.join(" "))
+    }
+
+    /// Class of the waybar module, for styling.
+    fn class(&self) -> &'static str {
+        match (self.count, self.attention) {
+            (0, _) => "empty",
+            (_, 0) => "normal",
+            _ => "attention",
+        }
+    }
+}
+
+/// Replaces control characters, so a title can't break the one line per update.
+fn escape_plain(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+/// Also doubles `#`, which starts a format in tmux.
+fn escape_tmux(text: &str) -> String {
+    escape_plain(text).replace('#', "##")
+}
+
+/// Escapes the text for the pango markup waybar renders.
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Summary in the output format, without the line break.
+fn status_line(summary: &Summary, format: StatusFormat, template: &str, config: &Config) -> String {
+    // JSON strings escape control characters on their own
+    let text = summary.render(template, str::to_owned);
+    match format {
+        StatusFormat::Plain => summary.render(template, escape_plain),
+        StatusFormat::Tmux => summary.render(template, escape_tmux),
+        StatusFormat::Waybar => json!({
+            "text": escape_markup(&text),
+            "tooltip": escape_markup(&summary.render(&config.status_bar.tooltip, str::to_owned)),
+            "class": summary.class(),
+            "alt": summary.class(),
+        })
+        .to_string(),
+        StatusFormat::I3bar => json!([{
+            "name": "tray-tui",
+            "full_text": text,
+            "urgent": summary.attention > 0,
+        }])
+        .to_string(),
+    }
+}
+
+/// Prints the summary line, then again every time it changes.
+pub async fn run(
+    status_format: StatusFormat,
+    template: Option<String>,
+    once: bool,
+    config: Config,
+) -> AppResult<()> {
+    let template = template.unwrap_or_else(|| config.status_bar.template.clone());
+    let (client, mut tray_rx) = connect().await?;
+
+    // i3bar reads a header followed by an endless array of status lines
+    let mut separator = "";
+    if status_format == StatusFormat::I3bar && !once && !write_stdout("{\"version\":1}\n[\n")? {
+        return Ok(());
+    }
+
+    let mut last = None;
+    loop {
+        let line = status_line(
+            &Summary::new(&client, &config)?,
+            status_format,
+            &template,
+            &config,
+        );
+        if last.as_ref() != Some(&line) {
+            if !write_stdout(&format!("{separator}{line}\n"))? || once {
+                return Ok(());
+            }
+            if status_format == StatusFormat::I3bar {
+                separator = ",";
+            }
+            last = Some(line);
+        }
+
+        match tray_rx.recv().await {
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => log::warn!("Skipped {} tray events", skipped),
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(titles: &[&str], attention: usize) -> Summary {
+        let titles: Vec<String> = titles.iter().map(|title| title.to_string()).collect();
+        Summary {
+            count: titles.len(),
+            attention,
+            glyphs: titles.clone(),
+            titles,
+        }
+    }
+
+    #[test]
+    fn template_fields_are_filled_in() {
+        let summary = summary(&["Steam", "Discord"], 1);
+        let line = summary.render("{count} ({attention}): {titles} | {glyphs}", str::to_owned);
+        assert_eq!(line, "2 (1): Steam, Discord | Steam Discord");
+    }
+
+    #[test]
+    fn class_follows_the_counts() {
+        assert_eq!(summary(&[], 0).class(), "empty");
+        assert_eq!(summary(&["Steam"], 0).class(), "normal");
+        assert_eq!(summary(&["Steam"], 1).class(), "attention");
+    }
+
+    #[test]
+    fn titles_are_escaped_for_each_format() {
+        assert_eq!(escape_plain("Wi-Fi\nhome\t5G"), "Wi-Fi home 5G");
+        assert_eq!(escape_tmux("#1 in\rqueue"), "##1 in queue");
+        assert_eq!(escape_markup("<b>R&D</b>"), "&lt;b&gt;R&amp;D&lt;/b&gt;");
+    }
+
+    #[test]
+    fn template_itself_is_not_escaped() {
+        let summary = summary(&["#1"], 0);
+        let line = status_line(
+            &summary,
+            StatusFormat::Tmux,
+            "#[bold]{titles}",
+            &Config::default(),
+        );
+        assert_eq!(line, "#[bold]##1");
+    }
+
+    #[test]
+    fn waybar_line_is_json_with_a_class() {
+        let summary = summary(&["<Steam>"], 1);
+        let line = status_line(
+            &summary,
+            StatusFormat::Waybar,
+            "{titles}",
+            &Config::default(),
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["text"], "&lt;Steam&gt;");
+        assert_eq!(value["class"], "attention");
+        assert_eq!(value["alt"], "attention");
+    }
+
+    #[test]
+    fn i3bar_line_is_urgent_on_attention() {
+        let line = status_line(
+            &summary(&["Steam\n"], 1),
+            StatusFormat::I3bar,
+            "{titles}",
+            &Config::default(),
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value[0]["full_text"], "Steam\n");
+        assert_eq!(value[0]["urgent"], true);
+        assert!(!line.contains('\n'));
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use system_tray::client::{Client, Event};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    app::AppResult,
+    commands::{
+        lock_items,
+        snapshot::{Change, EventSnapshot, ItemSnapshot},
+        write_stdout,
+    },
+};
+
+fn write_event(event: &EventSnapshot) -> AppResult<bool> {
+    let line = serde_json::to_string(event)? + "\n";
+    Ok(write_stdout(&line)?)
+}
+
+/// Prints every tray event as a line of JSON until the tray goes away.
+pub async fn run() -> AppResult<()> {
+    let client = Client::new().await?;
+    let mut tray_rx = client.subscribe();
+
+    // Items fetched before the subscription existed never show up as events,
+    // so the items already in the tray are printed as added
+    let initial: Vec<EventSnapshot> = {
+        let items = client.items();
+        let items = lock_items(&items)?;
+        items
+            .iter()
+            .map(|(key, item)| EventSnapshot {
+                key: key.clone(),
+                change: Change::Add {
+                    item: ItemSnapshot::new(key, item),
+                },
+            })
+            .collect()
+    };
+    // Keys announced as added, so an item isn't added twice
+    let mut added = HashSet::new();
+    for event in initial {
+        added.insert(event.key.clone());
+        if !write_event(&event)? {
+            return Ok(());
+        }
+    }
+
+    loop {
+        let event = match tray_rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                log::warn!("Skipped {} tray events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        match &event {
+            Event::Add(key, _) if !added.insert(key.clone()) => continue,
+            Event::Remove(key) => {
+                added.remove(key);
+            }
+            _ => {}
+        }
+
+        if !write_event(&EventSnapshot::from(&event))? {
+            return Ok(());
+        }
+    }
+}
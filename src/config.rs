@@ -1,14 +1,16 @@
+use crate::graphics::Graphics;
+use crate::pattern::glob_match;
 use crate::CMD;
 use crokey::{key, KeyCombination};
 use crossterm::event::{KeyCode, KeyModifiers};
-use std::error::Error;
-use std::str::FromStr;
 use ratatui::style::Color;
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
+use std::error::Error;
 use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum KeyBindEvent {
     FocusLeft,
@@ -19,9 +21,128 @@ pub enum KeyBindEvent {
     MenuDown,
     Quit,
     Activate,
+    ActivateItem,
+    SecondaryActivateItem,
+    ScrollItemUp,
+    ScrollItemDown,
+    Mnemonic,
+    Inspect,
+    Search,
+    CommandPalette,
+    ToggleSorting,
+    ToggleMouse,
+    ToggleScrollbar,
+    ToggleTooltip,
+    HideItem,
+    UnhideItems,
+    ReloadConfig,
+    Help,
     None,
 }
 
+impl KeyBindEvent {
+    /// Every action that can be bound, in the order they are listed to the user.
+    pub const ALL: [KeyBindEvent; 24] = [
+        KeyBindEvent::FocusLeft,
+        KeyBindEvent::FocusDown,
+        KeyBindEvent::FocusUp,
+        KeyBindEvent::FocusRight,
+        KeyBindEvent::MenuUp,
+        KeyBindEvent::MenuDown,
+        KeyBindEvent::Activate,
+        KeyBindEvent::ActivateItem,
+        KeyBindEvent::SecondaryActivateItem,
+        KeyBindEvent::ScrollItemUp,
+        KeyBindEvent::ScrollItemDown,
+        KeyBindEvent::Mnemonic,
+        KeyBindEvent::Search,
+        KeyBindEvent::Inspect,
+        KeyBindEvent::CommandPalette,
+        KeyBindEvent::ToggleSorting,
+        KeyBindEvent::ToggleMouse,
+        KeyBindEvent::ToggleScrollbar,
+        KeyBindEvent::ToggleTooltip,
+        KeyBindEvent::HideItem,
+        KeyBindEvent::UnhideItems,
+        KeyBindEvent::ReloadConfig,
+        KeyBindEvent::Help,
+        KeyBindEvent::Quit,
+    ];
+
+    /// Name of the action in the config and the command palette.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyBindEvent::FocusLeft => "focus_left",
+            KeyBindEvent::FocusDown => "focus_down",
+            KeyBindEvent::FocusUp => "focus_up",
+            KeyBindEvent::FocusRight => "focus_right",
+            KeyBindEvent::MenuUp => "menu_up",
+            KeyBindEvent::MenuDown => "menu_down",
+            KeyBindEvent::Quit => "quit",
+            KeyBindEvent::Activate => "activate",
+            KeyBindEvent::ActivateItem => "activate_item",
+            KeyBindEvent::SecondaryActivateItem => "secondary_activate_item",
+            KeyBindEvent::ScrollItemUp => "scroll_item_up",
+            KeyBindEvent::ScrollItemDown => "scroll_item_down",
+            KeyBindEvent::Mnemonic => "mnemonic",
+            KeyBindEvent::Inspect => "inspect",
+            KeyBindEvent::Search => "search",
+            KeyBindEvent::CommandPalette => "command_palette",
+            KeyBindEvent::ToggleSorting => "toggle_sorting",
+            KeyBindEvent::ToggleMouse => "toggle_mouse",
+            KeyBindEvent::ToggleScrollbar => "toggle_scrollbar",
+            KeyBindEvent::ToggleTooltip => "toggle_tooltip",
+            KeyBindEvent::HideItem => "hide_item",
+            KeyBindEvent::UnhideItems => "unhide_items",
+            KeyBindEvent::ReloadConfig => "reload_config",
+            KeyBindEvent::Help => "help",
+            KeyBindEvent::None => "none",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            KeyBindEvent::FocusLeft => "Focus the item on the left",
+            KeyBindEvent::FocusDown => "Focus the item below",
+            KeyBindEvent::FocusUp => "Focus the item above",
+            KeyBindEvent::FocusRight => "Focus the item on the right",
+            KeyBindEvent::MenuUp => "Select the previous menu entry",
+            KeyBindEvent::MenuDown => "Select the next menu entry",
+            KeyBindEvent::Quit => "Quit",
+            KeyBindEvent::Activate => "Activate the selected menu entry",
+            KeyBindEvent::ActivateItem => "Activate the focused item",
+            KeyBindEvent::SecondaryActivateItem => "Secondary activation of the focused item",
+            KeyBindEvent::ScrollItemUp => "Scroll the focused item up",
+            KeyBindEvent::ScrollItemDown => "Scroll the focused item down",
+            KeyBindEvent::Mnemonic => "Activate a menu entry by its access key",
+            KeyBindEvent::Inspect => "Inspect the focused item",
+            KeyBindEvent::Search => "Search the entries of all menus",
+            KeyBindEvent::CommandPalette => "Open the command palette",
+            KeyBindEvent::ToggleSorting => "Toggle sorting items by title",
+            KeyBindEvent::ToggleMouse => "Toggle mouse support",
+            KeyBindEvent::ToggleScrollbar => "Toggle the scrollbar",
+            KeyBindEvent::ToggleTooltip => "Toggle the tooltip panel of the focused item",
+            KeyBindEvent::HideItem => "Hide the focused item, or the item with the given id",
+            KeyBindEvent::UnhideItems => "Show hidden items again, or the one with the given id",
+            KeyBindEvent::ReloadConfig => "Reload the config file",
+            KeyBindEvent::Help => "Show the key bindings",
+            KeyBindEvent::None => "Do nothing",
+        }
+    }
+}
+
+/// Where items with the `Passive` status are shown.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PassiveItems {
+    /// Among the other items
+    #[default]
+    Show,
+    /// After all the other items
+    End,
+    Hide,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Config {
     #[serde(default = "columns")]
@@ -36,6 +157,9 @@ pub struct Config {
     #[serde(default = "sorting")]
     pub sorting: bool,
 
+    #[serde(default)]
+    pub passive_items: PassiveItems,
+
     #[serde(default = "colors")]
     pub colors: Colors,
 
@@ -45,8 +169,39 @@ pub struct Config {
     #[serde(default = "mouse")]
     pub mouse: bool,
 
+    #[serde(default = "tooltip")]
+    pub tooltip: bool,
+
+    #[serde(default = "item_scroll")]
+    pub item_scroll: bool,
+
+    #[serde(default = "scroll_delta")]
+    pub scroll_delta: i32,
+
+    #[serde(default)]
+    pub graphics: Graphics,
+
+    #[serde(default = "icon_theme")]
+    pub icon_theme: String,
+
+    #[serde(default = "nerd_font")]
+    pub nerd_font: bool,
+
+    #[serde(default = "glyphs", deserialize_with = "merge_glyphs")]
+    pub glyphs: Vec<GlyphRule>,
+
+    #[serde(default)]
+    pub alerts: Alerts,
+
+    #[serde(default = "status_bar")]
+    pub status_bar: StatusBar,
+
     #[serde(default = "key_map", deserialize_with = "merge_with_default")]
     pub key_map: HashMap<KeyCombination, KeyBindEvent>,
+
+    /// file the config was loaded from, `None` for the default location
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
 }
 
 fn merge_with_default<'de, D>(
@@ -70,7 +225,10 @@ where
                 KeyCombination::from_str(&k).map_err(serde::de::Error::custom)?
             };
             if config_map.contains_key(&kc) {
-                return Err(serde::de::Error::custom(format!("config: duplicate key binding detected for key: '{}'", k)))
+                return Err(serde::de::Error::custom(format!(
+                    "config: duplicate key binding detected for key: '{}'",
+                    k
+                )));
             }
 
             config_map.insert(kc, v);
@@ -84,6 +242,143 @@ where
     Ok(result)
 }
 
+fn merge_glyphs<'de, D>(deserializer: D) -> Result<Vec<GlyphRule>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // Rules from the config take precedence over the built-in ones
+    let mut result: Vec<GlyphRule> = Option::deserialize(deserializer)?.unwrap_or_default();
+    result.extend(glyphs());
+    Ok(result)
+}
+
+/// Nerd Font glyph shown in front of the title of matching tray items.
+///
+/// `id` and `icon_name` are case-insensitive globs, a rule needs at least one of them.
+/// Items needing attention are matched by their attention icon name.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GlyphRule {
+    #[serde(default)]
+    pub id: Option<String>,
+
+    #[serde(default)]
+    pub icon_name: Option<String>,
+
+    pub glyph: String,
+
+    /// glyph used instead while the item needs attention
+    #[serde(default)]
+    pub attention_glyph: Option<String>,
+}
+
+impl GlyphRule {
+    fn new(id: Option<&str>, icon_name: Option<&str>, glyph: &str) -> Self {
+        Self {
+            id: id.map(String::from),
+            icon_name: icon_name.map(String::from),
+            glyph: glyph.to_owned(),
+            attention_glyph: None,
+        }
+    }
+
+    fn with_attention(mut self, glyph: &str) -> Self {
+        self.attention_glyph = Some(glyph.to_owned());
+        self
+    }
+}
+
+/// Terminal escape used to send a desktop notification.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OscNotification {
+    #[default]
+    None,
+    /// `OSC 9`, supported by iTerm2, kitty, WezTerm, foot and others
+    Osc9,
+    /// `OSC 777`, supported by urxvt and VTE based terminals
+    Osc777,
+}
+
+/// What happens when a tray item starts needing attention.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Alerts {
+    /// ring the terminal bell
+    #[serde(default)]
+    pub bell: bool,
+
+    /// show the number of items needing attention in the terminal title
+    #[serde(default)]
+    pub title: bool,
+
+    #[serde(default)]
+    pub osc_notification: OscNotification,
+
+    /// send a notification through `org.freedesktop.Notifications`
+    #[serde(default)]
+    pub notification: bool,
+
+    /// overrides for items whose id matches, the first matching one is used
+    #[serde(default)]
+    pub items: Vec<AlertRule>,
+}
+
+/// Alert actions of the items whose id matches the case-insensitive glob `id`.
+/// Unset actions are taken from [Alerts].
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRule {
+    pub id: String,
+
+    #[serde(default)]
+    pub bell: Option<bool>,
+
+    #[serde(default)]
+    pub title: Option<bool>,
+
+    #[serde(default)]
+    pub osc_notification: Option<OscNotification>,
+
+    #[serde(default)]
+    pub notification: Option<bool>,
+}
+
+/// Alert actions resolved for one item.
+#[derive(Debug, Clone, Copy)]
+pub struct AlertActions {
+    pub bell: bool,
+    pub title: bool,
+    pub osc_notification: OscNotification,
+    pub notification: bool,
+}
+
+impl Alerts {
+    pub fn actions(&self, id: &str) -> AlertActions {
+        let rule = self.items.iter().find(|rule| glob_match(&rule.id, id));
+        AlertActions {
+            bell: rule.and_then(|r| r.bell).unwrap_or(self.bell),
+            title: rule.and_then(|r| r.title).unwrap_or(self.title),
+            osc_notification: rule
+                .and_then(|r| r.osc_notification)
+                .unwrap_or(self.osc_notification),
+            notification: rule
+                .and_then(|r| r.notification)
+                .unwrap_or(self.notification),
+        }
+    }
+}
+
+/// Line printed by the `status` subcommand.
+///
+/// Templates can use `{count}`, `{attention}`, `{titles}` and `{glyphs}`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StatusBar {
+    #[serde(default = "status_template")]
+    pub template: String,
+
+    /// tooltip of the waybar module
+    #[serde(default = "status_tooltip")]
+    pub tooltip: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Symbols {
     #[serde(default = "highlight_symbol")]
@@ -97,6 +392,27 @@ pub struct Symbols {
 
     #[serde(default = "node_no_children_symbol")]
     pub node_no_children_symbol: String,
+
+    #[serde(default = "checkbox_checked")]
+    pub checkbox_checked: String,
+
+    #[serde(default = "checkbox_unchecked")]
+    pub checkbox_unchecked: String,
+
+    #[serde(default = "radio_selected")]
+    pub radio_selected: String,
+
+    #[serde(default = "radio_unselected")]
+    pub radio_unselected: String,
+
+    #[serde(default = "status_active")]
+    pub status_active: String,
+
+    #[serde(default = "status_passive")]
+    pub status_passive: String,
+
+    #[serde(default = "status_attention")]
+    pub status_attention: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -130,6 +446,21 @@ pub struct Colors {
 
     #[serde(default = "black")]
     pub fg_highlighted: Color,
+
+    #[serde(default = "dark_gray")]
+    pub fg_disabled: Color,
+
+    #[serde(default = "yellow")]
+    pub fg_warning: Color,
+
+    #[serde(default = "red")]
+    pub fg_alert: Color,
+
+    #[serde(default = "dark_gray")]
+    pub fg_passive: Color,
+
+    #[serde(default = "red")]
+    pub border_fg_attention: Color,
 }
 
 impl Default for Symbols {
@@ -139,6 +470,22 @@ impl Default for Symbols {
             node_open_symbol: node_open_symbol(),
             node_closed_symbol: node_closed_symbol(),
             node_no_children_symbol: node_no_children_symbol(),
+            checkbox_checked: checkbox_checked(),
+            checkbox_unchecked: checkbox_unchecked(),
+            radio_selected: radio_selected(),
+            radio_unselected: radio_unselected(),
+            status_active: status_active(),
+            status_passive: status_passive(),
+            status_attention: status_attention(),
+        }
+    }
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self {
+            template: status_template(),
+            tooltip: status_tooltip(),
         }
     }
 }
@@ -147,13 +494,24 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             sorting: sorting(),
+            passive_items: PassiveItems::default(),
             symbols: symbols(),
             colors: colors(),
             columns: columns(),
             scrollbar: scrollbar(),
             min_height: min_height(),
             mouse: mouse(),
+            tooltip: tooltip(),
+            item_scroll: item_scroll(),
+            scroll_delta: scroll_delta(),
+            graphics: Graphics::default(),
+            icon_theme: icon_theme(),
+            nerd_font: nerd_font(),
+            glyphs: glyphs(),
+            alerts: Alerts::default(),
+            status_bar: status_bar(),
             key_map: key_map(),
+            path: None,
         }
     }
 }
@@ -174,10 +532,23 @@ impl Config {
             }
         };
 
-        let config = builder.build()?.try_deserialize::<Config>()?;
+        let mut config = builder.build()?.try_deserialize::<Config>()?;
+        config.path = path.clone();
         Ok(config)
     }
 
+    /// Key combinations bound to the action, sorted for display.
+    pub fn bindings(&self, event: KeyBindEvent) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .key_map
+            .iter()
+            .filter(|(_, e)| **e == event)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        keys.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+        keys
+    }
+
     fn get_default_config_path() -> Result<PathBuf, Box<dyn Error>> {
         match dirs::config_dir() {
             Some(conf_dir) => Ok(conf_dir.join(format!("{CMD}/config.toml"))),
@@ -201,6 +572,11 @@ impl Default for Colors {
             border_bg_focused: reset(),
             bg_highlighted: green(),
             fg_highlighted: black(),
+            fg_disabled: dark_gray(),
+            fg_warning: yellow(),
+            fg_alert: red(),
+            fg_passive: dark_gray(),
+            border_fg_attention: red(),
         }
     }
 }
@@ -224,6 +600,18 @@ const fn green() -> Color {
     Color::Green
 }
 
+const fn dark_gray() -> Color {
+    Color::DarkGray
+}
+
+const fn yellow() -> Color {
+    Color::Yellow
+}
+
+const fn red() -> Color {
+    Color::Red
+}
+
 const fn sorting() -> bool {
     false
 }
@@ -232,6 +620,18 @@ fn symbols() -> Symbols {
     Symbols::default()
 }
 
+fn status_bar() -> StatusBar {
+    StatusBar::default()
+}
+
+fn status_template() -> String {
+    String::from("{glyphs}")
+}
+
+fn status_tooltip() -> String {
+    String::from("{titles}")
+}
+
 fn highlight_symbol() -> String {
     String::new()
 }
@@ -248,6 +648,34 @@ fn node_no_children_symbol() -> String {
     String::from(" ")
 }
 
+fn checkbox_checked() -> String {
+    String::from("[x] ")
+}
+
+fn checkbox_unchecked() -> String {
+    String::from("[ ] ")
+}
+
+fn radio_selected() -> String {
+    String::from("(•) ")
+}
+
+fn radio_unselected() -> String {
+    String::from("( ) ")
+}
+
+fn status_active() -> String {
+    String::new()
+}
+
+fn status_passive() -> String {
+    String::new()
+}
+
+fn status_attention() -> String {
+    String::from(" ● ")
+}
+
 const fn columns() -> usize {
     3
 }
@@ -264,6 +692,40 @@ const fn mouse() -> bool {
     true
 }
 
+const fn tooltip() -> bool {
+    false
+}
+
+const fn item_scroll() -> bool {
+    false
+}
+
+const fn scroll_delta() -> i32 {
+    1
+}
+
+fn icon_theme() -> String {
+    String::from("hicolor")
+}
+
+const fn nerd_font() -> bool {
+    false
+}
+
+fn glyphs() -> Vec<GlyphRule> {
+    vec![
+        GlyphRule::new(Some("nm-applet"), None, "󰖩"),
+        GlyphRule::new(Some("blueman*"), None, "󰂯"),
+        GlyphRule::new(Some("pasystray"), None, "󰕾"),
+        GlyphRule::new(Some("steam"), None, "󰓓"),
+        GlyphRule::new(Some("*discord*"), None, "󰙯").with_attention("󰂞"),
+        GlyphRule::new(Some("*telegram*"), None, "").with_attention("󰂞"),
+        GlyphRule::new(None, Some("keepassxc-locked"), "󰌾"),
+        GlyphRule::new(Some("keepassxc"), None, "󰌆"),
+        GlyphRule::new(Some("udiskie"), None, "󰋊"),
+    ]
+}
+
 fn key_map() -> HashMap<KeyCombination, KeyBindEvent> {
     let mut map = HashMap::new();
     map.insert(key!(left), KeyBindEvent::FocusLeft);
@@ -282,6 +744,81 @@ fn key_map() -> HashMap<KeyCombination, KeyBindEvent> {
     map.insert(key!(q), KeyBindEvent::Quit);
     map.insert(key!(enter), KeyBindEvent::Activate);
     map.insert(key!(space), KeyBindEvent::Activate);
+    map.insert(key!(a), KeyBindEvent::ActivateItem);
+    map.insert(key!(shift - a), KeyBindEvent::SecondaryActivateItem);
+    map.insert(key!(ctrl - up), KeyBindEvent::ScrollItemUp);
+    map.insert(key!(ctrl - down), KeyBindEvent::ScrollItemDown);
+    map.insert(key!(m), KeyBindEvent::Mnemonic);
+    map.insert(key!(i), KeyBindEvent::Inspect);
+    map.insert(key!(t), KeyBindEvent::ToggleTooltip);
+    map.insert(key!('/'), KeyBindEvent::Search);
+    map.insert(key!(':'), KeyBindEvent::CommandPalette);
+    map.insert(key!(ctrl - p), KeyBindEvent::CommandPalette);
+    map.insert(key!('?'), KeyBindEvent::Help);
 
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alerts(toml: &str) -> Alerts {
+        config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .and_then(config::Config::try_deserialize)
+            .unwrap()
+    }
+
+    #[test]
+    fn items_without_a_rule_use_the_defaults() {
+        let alerts = alerts("bell = true\nosc_notification = \"osc9\"");
+        let actions = alerts.actions("nm-applet");
+        assert!(actions.bell);
+        assert!(!actions.title);
+        assert_eq!(actions.osc_notification, OscNotification::Osc9);
+        assert!(!actions.notification);
+    }
+
+    #[test]
+    fn rules_override_only_the_actions_they_set() {
+        let alerts = alerts(
+            r#"
+            bell = true
+            title = true
+
+            [[items]]
+            id = "*discord*"
+            bell = false
+            notification = true
+            "#,
+        );
+        let actions = alerts.actions("Discord");
+        assert!(!actions.bell);
+        assert!(actions.title);
+        assert!(actions.notification);
+        assert!(alerts.actions("steam").bell);
+    }
+
+    #[test]
+    fn first_matching_rule_is_used() {
+        let alerts = alerts(
+            r#"
+            [[items]]
+            id = "chrome_status_icon_*"
+            bell = true
+
+            [[items]]
+            id = "*"
+            bell = false
+            title = true
+            "#,
+        );
+        let actions = alerts.actions("chrome_status_icon_1");
+        assert!(actions.bell);
+        assert!(!actions.title);
+        assert!(!alerts.actions("steam").bell);
+        assert!(alerts.actions("steam").title);
+    }
+}
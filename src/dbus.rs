@@ -0,0 +1,118 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::time::timeout;
+use zbus::Connection;
+
+use crate::app::AppResult;
+
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const ITEM_OBJECT: &str = "/StatusNotifierItem";
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_OBJECT: &str = "/org/freedesktop/Notifications";
+
+/// Time to wait for an item to reply, some items only reply after their popup is closed.
+const CALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Splits an item address (the key used by [system_tray::client::Client]) into
+/// its bus name and object path.
+fn parse_address(address: &str) -> (&str, String) {
+    address
+        .split_once('/')
+        .map_or((address, String::from(ITEM_OBJECT)), |(d, p)| {
+            (d, format!("/{p}"))
+        })
+}
+
+/// Calls a `org.kde.StatusNotifierItem` method that isn't exposed by [system_tray::client::Client].
+async fn call_item_method<B>(
+    connection: &Connection,
+    address: &str,
+    method: &str,
+    body: &B,
+) -> AppResult<()>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let (destination, path) = parse_address(address);
+    let call = connection.call_method(
+        Some(destination),
+        path.as_str(),
+        Some(ITEM_INTERFACE),
+        method,
+        body,
+    );
+
+    match timeout(CALL_TIMEOUT, call).await {
+        Ok(res) => res.map(|_| ()).map_err(Into::into),
+        Err(_) => {
+            log::debug!("Timed out waiting for {} reply from {}", method, address);
+            Ok(())
+        }
+    }
+}
+
+/// Asks the item to show its own context menu at the given screen coordinates.
+pub async fn context_menu(connection: &Connection, address: &str, x: i32, y: i32) -> AppResult<()> {
+    call_item_method(connection, address, "ContextMenu", &(x, y)).await
+}
+
+/// Orientation of a scroll sent to an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollOrientation {
+    Vertical,
+    Horizontal,
+}
+
+impl ScrollOrientation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ScrollOrientation::Vertical => "Vertical",
+            ScrollOrientation::Horizontal => "Horizontal",
+        }
+    }
+}
+
+/// Sends a scroll of `delta` steps to the item, used by volume and brightness applets.
+pub async fn scroll(
+    connection: &Connection,
+    address: &str,
+    delta: i32,
+    orientation: ScrollOrientation,
+) -> AppResult<()> {
+    call_item_method(
+        connection,
+        address,
+        "Scroll",
+        &(delta, orientation.as_str()),
+    )
+    .await
+}
+
+/// Shows a desktop notification through the notification server.
+pub async fn notify(
+    connection: &Connection,
+    summary: &str,
+    body: &str,
+    icon: &str,
+) -> AppResult<()> {
+    let hints: HashMap<&str, zbus::zvariant::Value> = HashMap::new();
+    let actions: Vec<&str> = Vec::new();
+    // No replaced notification and the server's default timeout
+    let body = (crate::CMD, 0u32, icon, summary, body, actions, hints, -1i32);
+    let call = connection.call_method(
+        Some(NOTIFICATIONS_NAME),
+        NOTIFICATIONS_OBJECT,
+        Some(NOTIFICATIONS_NAME),
+        "Notify",
+        &body,
+    );
+
+    match timeout(CALL_TIMEOUT, call).await {
+        Ok(res) => res.map(|_| ()).map_err(Into::into),
+        Err(_) => {
+            log::debug!("Timed out waiting for the notification server");
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use system_tray::{
+    item::StatusNotifierItem,
+    menu::{MenuItem, TrayMenu},
+};
+
+use crate::wrappers::{parse_mnemonic, GetTitle, Id, MenuItemKind};
+
+/// Separator between the item title and the labels of a menu path.
+pub const PATH_SEPARATOR: &str = " › ";
+
+/// An activatable menu entry of a tray item, flattened out of its menu tree.
+#[derive(Debug, Clone)]
+pub struct MenuEntry {
+    /// key of the tray item
+    pub key: String,
+    /// dbusmenu ids leading to the entry
+    pub ids: Vec<Id>,
+    /// item title followed by the labels leading to the entry, without access keys
+    pub labels: Vec<String>,
+    pub is_submenu: bool,
+}
+
+impl MenuEntry {
+    /// Path of the entry like `NetworkManager › VPN Connections › work`.
+    pub fn path(&self) -> String {
+        self.labels.join(PATH_SEPARATOR)
+    }
+}
+
+/// Every entry of the item menu that can be activated, skipping separators and
+/// disabled or hidden entries along with their children.
+pub fn menu_entries(
+    key: &str,
+    (item, menu): &(StatusNotifierItem, Option<TrayMenu>),
+) -> Vec<MenuEntry> {
+    let mut entries = Vec::new();
+    if let Some(menu) = menu {
+        let parent = MenuEntry {
+            key: key.to_owned(),
+            ids: Vec::new(),
+            labels: vec![item.get_title().to_owned()],
+            is_submenu: true,
+        };
+        collect(&menu.submenus, &parent, &mut entries);
+    }
+    entries
+}
+
+fn collect(items: &[MenuItem], parent: &MenuEntry, entries: &mut Vec<MenuEntry>) {
+    for item in items.iter().filter(|item| item.is_activatable()) {
+        let (label, _) = parse_mnemonic(item.label.as_deref().unwrap_or_default());
+
+        let mut entry = parent.clone();
+        entry.ids.push(item.id);
+        entry.labels.push(label);
+        entry.is_submenu = item.is_submenu();
+
+        entries.push(entry.clone());
+        collect(&item.submenu, &entry, entries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, item, separator, submenu, with_menu};
+
+    fn paths(entries: &[MenuEntry]) -> Vec<String> {
+        entries.iter().map(MenuEntry::path).collect()
+    }
+
+    fn network_manager(entries: Vec<MenuItem>) -> (StatusNotifierItem, Option<TrayMenu>) {
+        let item = StatusNotifierItem {
+            title: Some(String::from("NetworkManager")),
+            ..item("nm-applet")
+        };
+        with_menu(item, entries)
+    }
+
+    #[test]
+    fn nested_entries_are_flattened_into_paths() {
+        let vpn = submenu(2, "_VPN Connections", vec![entry(3, "work")]);
+        let pair = network_manager(vec![entry(1, "_Wi-Fi"), vpn]);
+        let entries = menu_entries(":1.42", &pair);
+        assert_eq!(
+            paths(&entries),
+            [
+                "NetworkManager › Wi-Fi",
+                "NetworkManager › VPN Connections",
+                "NetworkManager › VPN Connections › work",
+            ]
+        );
+        assert_eq!(entries[2].ids, [2, 3]);
+        assert_eq!(entries[2].key, ":1.42");
+        assert!(entries[1].is_submenu);
+        assert!(!entries[2].is_submenu);
+    }
+
+    #[test]
+    fn separators_and_disabled_entries_are_skipped() {
+        let disabled = MenuItem {
+            enabled: false,
+            ..submenu(3, "VPN Connections", vec![entry(4, "work")])
+        };
+        let hidden = MenuItem {
+            visible: false,
+            ..entry(5, "Hidden")
+        };
+        let pair = network_manager(vec![entry(1, "Wi-Fi"), separator(2), disabled, hidden]);
+        assert_eq!(
+            paths(&menu_entries(":1.42", &pair)),
+            ["NetworkManager › Wi-Fi"]
+        );
+    }
+
+    #[test]
+    fn items_without_a_menu_have_no_entries() {
+        assert!(menu_entries(":1.42", &(item("steam"), None)).is_empty());
+    }
+}
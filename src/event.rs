@@ -1,18 +1,16 @@
-use std::collections::HashMap;
-
-use crokey::KeyCombination;
-use crossterm::event::{Event as CrosstermEvent, MouseEvent, KeyEventKind};
+use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
 use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 
 use crate::app::AppResult;
-use crate::config::KeyBindEvent;
 
 /// Terminal events.
 #[derive(Clone, Copy, Debug)]
 pub enum Event {
-    /// Key press.
-    Key(KeyBindEvent),
+    /// Key press, mapped to a [KeyBindEvent] by the handler depending on the input mode.
+    ///
+    /// [KeyBindEvent]: crate::config::KeyBindEvent
+    Key(KeyEvent),
     /// Mouse click/scroll.
     Mouse(MouseEvent),
     /// Terminal resize.
@@ -33,9 +31,15 @@ pub struct EventHandler {
     handler: tokio::task::JoinHandle<()>,
 }
 
+impl Default for EventHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EventHandler {
     /// Constructs a new instance of [`EventHandler`].
-    pub fn new(use_mouse: bool, keymap: HashMap<KeyCombination, KeyBindEvent>) -> Self {
+    pub fn new() -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
         let _sender = sender.clone();
         let handler = tokio::spawn(async move {
@@ -50,16 +54,11 @@ impl EventHandler {
                     match evt {
                       CrosstermEvent::Key(key) => {
                         if key.kind == KeyEventKind::Press {
-                          let key_bind = KeyCombination::from(key);
-                          if let Some(event) = keymap.get(&key_bind) {
-                            _sender.send(Event::Key(*event)).unwrap();
-                          }
+                          _sender.send(Event::Key(key)).unwrap();
                         }
                       },
                       CrosstermEvent::Mouse(mouse) => {
-                        if use_mouse {
-                            _sender.send(Event::Mouse(mouse)).unwrap();
-                        }
+                        _sender.send(Event::Mouse(mouse)).unwrap();
                       },
                       CrosstermEvent::Resize(x, y) => {
                         _sender.send(Event::Resize(x, y)).unwrap();
@@ -91,9 +90,6 @@ impl EventHandler {
         self.receiver
             .recv()
             .await
-            .ok_or(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "This is an IO error",
-            )))
+            .ok_or(Box::new(std::io::Error::other("This is an IO error")))
     }
 }
@@ -0,0 +1,74 @@
+//! Tray items and menus for tests.
+
+use system_tray::{
+    item::{Category, Status, StatusNotifierItem},
+    menu::{MenuItem, MenuType, TrayMenu},
+};
+
+/// Active item with the given id and nothing else set.
+pub fn item(id: &str) -> StatusNotifierItem {
+    StatusNotifierItem {
+        id: id.to_owned(),
+        category: Category::ApplicationStatus,
+        title: None,
+        status: Status::Active,
+        window_id: 0,
+        icon_theme_path: None,
+        icon_name: None,
+        icon_pixmap: None,
+        overlay_icon_name: None,
+        overlay_icon_pixmap: None,
+        attention_icon_name: None,
+        attention_icon_pixmap: None,
+        attention_movie_name: None,
+        tool_tip: None,
+        item_is_menu: false,
+        menu: None,
+    }
+}
+
+/// Visible and enabled menu entry.
+pub fn entry(id: i32, label: &str) -> MenuItem {
+    MenuItem {
+        id,
+        label: Some(label.to_owned()),
+        enabled: true,
+        visible: true,
+        ..MenuItem::default()
+    }
+}
+
+pub fn separator(id: i32) -> MenuItem {
+    MenuItem {
+        id,
+        menu_type: MenuType::Separator,
+        enabled: true,
+        visible: true,
+        ..MenuItem::default()
+    }
+}
+
+/// Entry opening a submenu with the given children.
+pub fn submenu(id: i32, label: &str, children: Vec<MenuItem>) -> MenuItem {
+    MenuItem {
+        submenu: children,
+        children_display: Some(String::from("submenu")),
+        ..entry(id, label)
+    }
+}
+
+pub fn menu(entries: Vec<MenuItem>) -> TrayMenu {
+    TrayMenu {
+        id: 1,
+        submenus: entries,
+    }
+}
+
+/// Item exporting the menu, as stored in the client's items map.
+pub fn with_menu(
+    mut item: StatusNotifierItem,
+    entries: Vec<MenuItem>,
+) -> (StatusNotifierItem, Option<TrayMenu>) {
+    item.menu = Some(String::from("/MenuBar"));
+    (item, Some(menu(entries)))
+}
@@ -0,0 +1,330 @@
+use std::io::{self, Write};
+
+use crossterm::{cursor::MoveTo, queue, terminal};
+use serde::Deserialize;
+
+use crate::icon::{Icon, ICON_WIDTH};
+
+/// Graphics protocol selected in the config.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Graphics {
+    /// Detect the best protocol supported by the terminal
+    #[default]
+    Auto,
+    Kitty,
+    Sixel,
+    Halfblocks,
+    /// Don't draw icons at all
+    None,
+}
+
+/// Protocol used to draw icons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// Images placed with the kitty graphics protocol
+    Kitty,
+    /// Images encoded as DEC sixels
+    Sixel,
+    /// Unicode half blocks in truecolor
+    Halfblocks,
+}
+
+impl Graphics {
+    /// Resolves the protocol to use, `None` if icons can't or shouldn't be drawn.
+    pub fn protocol(self) -> Option<Protocol> {
+        match self {
+            Graphics::Auto => detect(),
+            Graphics::Kitty => Some(Protocol::Kitty),
+            Graphics::Sixel => Some(Protocol::Sixel),
+            Graphics::Halfblocks => Some(Protocol::Halfblocks),
+            Graphics::None => None,
+        }
+    }
+}
+
+impl Protocol {
+    /// Whether the protocol draws real images, which ratatui's buffer can't hold.
+    pub fn is_image(self) -> bool {
+        matches!(self, Protocol::Kitty | Protocol::Sixel)
+    }
+}
+
+/// Guesses the graphics protocol from the environment the terminal sets.
+fn detect() -> Option<Protocol> {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let term_program = var("TERM_PROGRAM");
+
+    if std::env::var_os("KITTY_WINDOW_ID").is_some()
+        || term.contains("kitty")
+        || term.contains("ghostty")
+        || term_program == "WezTerm"
+    {
+        return Some(Protocol::Kitty);
+    }
+
+    if std::env::var_os("KONSOLE_VERSION").is_some()
+        || term.starts_with("foot")
+        || term.contains("mlterm")
+        || term_program == "iTerm.app"
+    {
+        return Some(Protocol::Sixel);
+    }
+
+    let colorterm = var("COLORTERM");
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return Some(Protocol::Halfblocks);
+    }
+
+    None
+}
+
+/// Size of a terminal cell in pixels, with a guess when the terminal doesn't report it.
+pub fn cell_size() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns) as u32,
+            (size.height / size.rows) as u32,
+        ),
+        _ => (10, 20),
+    }
+}
+
+/// An icon image placed at a cell of the terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub x: u16,
+    pub y: u16,
+    pub icon: Icon,
+}
+
+/// Draws icon images over the rendered frame.
+///
+/// Ratatui doesn't know about the images, so cells under them are skipped while rendering and
+/// the images are written after each frame, only when something moved.
+#[derive(Debug)]
+pub struct ImageLayer {
+    protocol: Option<Protocol>,
+    /// placements of the frame being rendered
+    pub placements: Vec<Placement>,
+    /// placements currently on screen
+    drawn: Vec<Placement>,
+}
+
+impl ImageLayer {
+    pub fn new(protocol: Option<Protocol>) -> Self {
+        Self {
+            protocol: protocol.filter(|p| p.is_image()),
+            placements: Vec::new(),
+            drawn: Vec::new(),
+        }
+    }
+
+    /// Forces images to be drawn again, e.g. after the terminal was cleared.
+    pub fn invalidate(&mut self) {
+        self.drawn.clear();
+    }
+
+    /// Writes the placements of the last rendered frame.
+    pub fn flush(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let Some(protocol) = self.protocol else {
+            return Ok(());
+        };
+        let placements = std::mem::take(&mut self.placements);
+        if placements == self.drawn {
+            return Ok(());
+        }
+
+        if protocol == Protocol::Kitty {
+            // Remove all images of the previous frame, including their data
+            write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        }
+        // The cursor ends up below a sixel image, which scrolls the screen on the last row
+        let rows = terminal::size().map_or(u16::MAX, |(_, rows)| rows);
+        let fits = |placement: &&Placement| protocol == Protocol::Kitty || placement.y + 1 < rows;
+        for placement in placements.iter().filter(fits) {
+            queue!(out, MoveTo(placement.x, placement.y))?;
+            match protocol {
+                Protocol::Kitty => write_kitty(out, &placement.icon)?,
+                _ => write_sixel(out, &placement.icon)?,
+            }
+        }
+        out.flush()?;
+
+        self.drawn = placements;
+        Ok(())
+    }
+
+    /// Removes images that ratatui won't clear by itself.
+    pub fn clear(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.protocol == Some(Protocol::Kitty) {
+            write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+            out.flush()?;
+        }
+        self.drawn.clear();
+        Ok(())
+    }
+}
+
+/// Transmits and displays the icon at the cursor, scaled to the icon cells.
+fn write_kitty(out: &mut impl Write, icon: &Icon) -> io::Result<()> {
+    let data: Vec<u8> = icon.pixels.iter().flatten().copied().collect();
+    let encoded = base64(&data);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},c={},r=1,C=1,q=2,m={};",
+                icon.width, icon.height, ICON_WIDTH, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+
+    Ok(())
+}
+
+/// Encodes the icon as sixels with a 6x6x6 color cube, leaving transparent pixels untouched.
+fn write_sixel(out: &mut impl Write, icon: &Icon) -> io::Result<()> {
+    let level = |c: u8| (c as u16 * 5 + 127) / 255;
+    let index = |[r, g, b, a]: [u8; 4]| {
+        (a >= 128).then(|| (level(r) * 36 + level(g) * 6 + level(b)) as usize)
+    };
+    let indices: Vec<Option<usize>> = icon.pixels.iter().map(|p| index(*p)).collect();
+
+    // P2=1 keeps pixels that are not set
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", icon.width, icon.height)?;
+
+    let mut used = [false; 216];
+    indices.iter().flatten().for_each(|&i| used[i] = true);
+    for (i, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        let percent = |l: usize| l * 100 / 5;
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        )?;
+    }
+
+    let width = icon.width as usize;
+    let bands = (icon.height as usize).div_ceil(6);
+    for band in 0..bands {
+        let rows = band * 6..((band + 1) * 6).min(icon.height as usize);
+        for (color, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            let sixels: Vec<u8> = (0..width)
+                .map(|x| {
+                    rows.clone()
+                        .fold(0u8, |bits, y| match indices[y * width + x] {
+                            Some(i) if i == color => bits | 1 << (y - band * 6),
+                            _ => bits,
+                        })
+                })
+                .collect();
+            if sixels.iter().all(|bits| *bits == 0) {
+                continue;
+            }
+
+            write!(out, "#{}", color)?;
+            for run in sixels.chunk_by(|a, b| a == b) {
+                let ch = (run[0] + 63) as char;
+                match run.len() {
+                    1..=3 => write!(out, "{}", ch.to_string().repeat(run.len()))?,
+                    len => write!(out, "!{}{}", len, ch)?,
+                }
+            }
+            // Return to the start of the band for the next color
+            write!(out, "$")?;
+        }
+        // No newline after the last band, it would move the cursor below the image
+        if band + 1 < bands {
+            write!(out, "-")?;
+        }
+    }
+
+    write!(out, "\x1b\\")
+}
+
+/// Standard base64 with padding.
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(ALPHABET[(n >> (18 - i * 6) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn base64_uses_the_whole_alphabet() {
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(base64(&[0, 0, 0, 0xff]), "AAAA/w==");
+    }
+
+    fn sixel(icon: &Icon) -> String {
+        let mut out = Vec::new();
+        write_sixel(&mut out, icon).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn sixel_image_ends_without_a_newline() {
+        let red = [255, 0, 0, 255];
+        let icon = Icon {
+            width: 2,
+            height: 8,
+            pixels: vec![red; 16],
+        };
+        // two bands of one color, separated by a single graphics newline
+        assert_eq!(
+            sixel(&icon),
+            "\x1bP0;1;0q\"1;1;2;8#180;2;100;0;0#180~~$-#180BB$\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_skips_transparent_pixels() {
+        let icon = Icon {
+            width: 3,
+            height: 1,
+            pixels: vec![[0, 0, 255, 255], [0, 0, 0, 0], [0, 0, 255, 200]],
+        };
+        assert_eq!(sixel(&icon), "\x1bP0;1;0q\"1;1;3;1#5;2;0;0;100#5@?@$\x1b\\");
+    }
+}
@@ -1,12 +1,220 @@
 use crate::{
-    app::{App, AppResult, FocusDirection},
+    app::{App, AppResult, FocusDirection, ItemActivation, Mode},
     config::KeyBindEvent,
+    dbus::ScrollOrientation,
+    inspector::copy_to_clipboard,
+    palette::{matching_commands, Command, Palette},
+    search::Search,
 };
-use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
-use ratatui::layout::Position;
+use crokey::KeyCombination;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Margin, Position, Rect};
+use std::io;
 
 /// Handles the key events and updates the state of [`App`].
-pub async fn handle_key_events(key_bind_event: KeyBindEvent, app: &mut App) -> AppResult<()> {
+///
+/// In normal mode keys are mapped to [`KeyBindEvent`]s through the configured key map,
+/// other modes take the raw keys.
+pub async fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    match &app.mode {
+        Mode::Normal => {
+            let key_bind = KeyCombination::from(key_event);
+            if let Some(event) = app.config.key_map.get(&key_bind).copied() {
+                handle_key_bind_event(event, app).await?;
+            }
+        }
+        Mode::Mnemonic(_) => handle_mnemonic(key_event, app).await,
+        Mode::Inspector(_) => handle_inspector(key_event, app)?,
+        Mode::Search(_) => handle_search(key_event, app).await,
+        Mode::Palette(_) => handle_palette(key_event, app).await?,
+        Mode::Help(scroll) => {
+            app.mode = match key_event.code {
+                KeyCode::Down | KeyCode::Char('j') => Mode::Help(scroll.saturating_add(1)),
+                KeyCode::Up | KeyCode::Char('k') => Mode::Help(scroll.saturating_sub(1)),
+                _ => Mode::Normal,
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// Activates the entry whose access key was pressed, staying in mnemonic mode
+/// when it opened a submenu.
+async fn handle_mnemonic(key_event: KeyEvent, app: &mut App) {
+    let Mode::Mnemonic(scope) = std::mem::take(&mut app.mode) else {
+        return;
+    };
+    let KeyCode::Char(ch) = key_event.code else {
+        return;
+    };
+
+    if let Some(opened) = app.activate_mnemonic(&scope, ch).await {
+        app.mode = Mode::Mnemonic(opened);
+    }
+}
+
+/// Scrolls the inspector fields and copies the selected one, any other key closes it.
+fn handle_inspector(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let Mode::Inspector(inspector) = &app.mode else {
+        return Ok(());
+    };
+    let Some(fields) = app.inspector_fields(inspector) else {
+        // The item is gone
+        app.mode = Mode::Normal;
+        return Ok(());
+    };
+    let last = fields.len().saturating_sub(1);
+    let selected = inspector.selected.min(last);
+
+    let selected = match key_event.code {
+        KeyCode::Down | KeyCode::Char('j') => (selected + 1).min(last),
+        KeyCode::Up | KeyCode::Char('k') => selected.saturating_sub(1),
+        KeyCode::PageDown => (selected + 10).min(last),
+        KeyCode::PageUp => selected.saturating_sub(10),
+        KeyCode::Home | KeyCode::Char('g') => 0,
+        KeyCode::End | KeyCode::Char('G') => last,
+        KeyCode::Char('y') => {
+            if let Some(field) = fields.get(selected) {
+                copy_to_clipboard(&mut io::stdout(), &field.value)?;
+            }
+            selected
+        }
+        _ => {
+            app.mode = Mode::Normal;
+            return Ok(());
+        }
+    };
+
+    if let Mode::Inspector(inspector) = &mut app.mode {
+        inspector.selected = selected;
+    }
+    Ok(())
+}
+
+/// Edits the search query, moves through the results and activates the chosen one.
+async fn handle_search(key_event: KeyEvent, app: &mut App) {
+    let Mode::Search(search) = &mut app.mode else {
+        return;
+    };
+    let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+
+    match key_event.code {
+        KeyCode::Esc => app.mode = Mode::Normal,
+        KeyCode::Enter => {
+            let Mode::Search(search) = std::mem::take(&mut app.mode) else {
+                return;
+            };
+            let results = app.search_results(&search.query);
+            if let Some(result) = results.get(search.selected) {
+                let entry = result.entry.clone();
+                app.activate_menu_entry(&entry.key, &entry.ids).await;
+            }
+        }
+        KeyCode::Down | KeyCode::Tab => search.selected += 1,
+        KeyCode::Char('n') if ctrl => search.selected += 1,
+        KeyCode::Up | KeyCode::BackTab => search.selected = search.selected.saturating_sub(1),
+        KeyCode::Char('p') if ctrl => search.selected = search.selected.saturating_sub(1),
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.selected = 0;
+        }
+        KeyCode::Char(ch) if !ctrl => {
+            search.query.push(ch);
+            search.selected = 0;
+        }
+        _ => {}
+    }
+
+    // Keep the selection on a result
+    if let Mode::Search(search) = &app.mode {
+        let count = app.search_results(&search.query).len();
+        let selected = search.selected.min(count.saturating_sub(1));
+        if let Mode::Search(search) = &mut app.mode {
+            search.selected = selected;
+        }
+    }
+}
+
+/// Edits the palette input, moves through the matching commands and runs the chosen one.
+async fn handle_palette(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    let Mode::Palette(palette) = &mut app.mode else {
+        return Ok(());
+    };
+    let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+
+    match key_event.code {
+        KeyCode::Esc => app.mode = Mode::Normal,
+        KeyCode::Enter => {
+            let Mode::Palette(palette) = std::mem::take(&mut app.mode) else {
+                return Ok(());
+            };
+            let (_, args) = palette.split_input();
+            let command = matching_commands(&palette)
+                .get(palette.selected)
+                .map(|m| m.command);
+            if let Some(command) = command {
+                if let Err(error) = run_command(command, args, app).await? {
+                    // Keep the palette open to show what went wrong
+                    app.mode = Mode::Palette(Palette {
+                        error: Some(error),
+                        ..palette
+                    });
+                }
+            }
+            return Ok(());
+        }
+        KeyCode::Down | KeyCode::Tab => palette.selected += 1,
+        KeyCode::Char('n') if ctrl => palette.selected += 1,
+        KeyCode::Up | KeyCode::BackTab => palette.selected = palette.selected.saturating_sub(1),
+        KeyCode::Char('p') if ctrl => palette.selected = palette.selected.saturating_sub(1),
+        KeyCode::Backspace => {
+            palette.input.pop();
+            palette.selected = 0;
+            palette.error = None;
+        }
+        KeyCode::Char(ch) if !ctrl => {
+            palette.input.push(ch);
+            palette.selected = 0;
+            palette.error = None;
+        }
+        _ => {}
+    }
+
+    // Keep the selection on a command
+    if let Mode::Palette(palette) = &mut app.mode {
+        let count = matching_commands(palette).len();
+        palette.selected = palette.selected.min(count.saturating_sub(1));
+    }
+    Ok(())
+}
+
+/// Runs a palette command with the typed arguments.
+///
+/// The inner error is shown to the user in the palette.
+async fn run_command(command: Command, args: &str, app: &mut App) -> AppResult<Result<(), String>> {
+    let arg = Some(args).filter(|a| !a.is_empty());
+    match command {
+        Command::Columns => match args.parse::<usize>() {
+            Ok(columns) if columns > 0 => app.set_columns(columns),
+            _ => return Ok(Err(String::from("columns takes a positive number"))),
+        },
+        Command::KeyBind(KeyBindEvent::HideItem) => {
+            app.hide_item(arg);
+        }
+        Command::KeyBind(KeyBindEvent::UnhideItems) => app.unhide_items(arg),
+        Command::KeyBind(KeyBindEvent::ReloadConfig) => {
+            if let Err(e) = app.reload_config() {
+                return Ok(Err(e.to_string()));
+            }
+        }
+        Command::KeyBind(event) => handle_key_bind_event(event, app).await?,
+    }
+    Ok(Ok(()))
+}
+
+/// Handles the [`KeyBindEvent`] and updates the state of [`App`].
+pub async fn handle_key_bind_event(key_bind_event: KeyBindEvent, app: &mut App) -> AppResult<()> {
     match key_bind_event {
         // Exit application on `ESC` or `q`
         KeyBindEvent::Quit => {
@@ -24,26 +232,73 @@ pub async fn handle_key_events(key_bind_event: KeyBindEvent, app: &mut App) -> A
         KeyBindEvent::FocusUp => {
             app.move_focus(FocusDirection::Up);
         }
-        _ => {}
-    }
-    let tree_state = app.get_focused_tree_state_mut();
-    if tree_state.is_none() {
-        return Ok(());
-    }
-    let mut tree_state = &mut tree_state.unwrap();
-    match key_bind_event {
+        KeyBindEvent::ActivateItem => {
+            if let Some(key) = app.get_focused_sni_key() {
+                let _ = app.activate_item(key, ItemActivation::Primary).await;
+            }
+        }
+        KeyBindEvent::SecondaryActivateItem => {
+            if let Some(key) = app.get_focused_sni_key() {
+                let _ = app.activate_item(key, ItemActivation::Secondary).await;
+            }
+        }
+        KeyBindEvent::ScrollItemUp | KeyBindEvent::ScrollItemDown => {
+            let delta = match key_bind_event {
+                KeyBindEvent::ScrollItemUp => app.config.scroll_delta,
+                _ => -app.config.scroll_delta,
+            };
+            if let Some(key) = app.get_focused_sni_key() {
+                let _ = app
+                    .scroll_item(key, delta, ScrollOrientation::Vertical)
+                    .await;
+            }
+        }
         KeyBindEvent::Activate => {
-            let ids = tree_state.selected().to_vec();
-            let _ = app.activate_menu_item(&ids, &mut tree_state).await;
+            let _ = app.activate().await;
         }
         KeyBindEvent::MenuDown => {
-            if !tree_state.key_down() {
-                tree_state.select_first();
-            }
+            app.move_menu_selection(FocusDirection::Down);
         }
         KeyBindEvent::MenuUp => {
-            if !tree_state.key_up() {
-                tree_state.select_last();
+            app.move_menu_selection(FocusDirection::Up);
+        }
+        KeyBindEvent::Mnemonic => {
+            app.enter_mnemonic_mode();
+        }
+        KeyBindEvent::Inspect => {
+            app.open_inspector();
+        }
+        KeyBindEvent::Search => {
+            app.mode = Mode::Search(Search::default());
+        }
+        KeyBindEvent::CommandPalette => {
+            app.mode = Mode::Palette(Palette::default());
+        }
+        KeyBindEvent::Help => {
+            app.mode = Mode::Help(0);
+        }
+        KeyBindEvent::ToggleSorting => {
+            app.config.sorting = !app.config.sorting;
+            app.update();
+        }
+        KeyBindEvent::ToggleMouse => {
+            app.config.mouse = !app.config.mouse;
+        }
+        KeyBindEvent::ToggleScrollbar => {
+            app.config.scrollbar = !app.config.scrollbar;
+        }
+        KeyBindEvent::ToggleTooltip => {
+            app.config.tooltip = !app.config.tooltip;
+        }
+        KeyBindEvent::HideItem => {
+            app.hide_item(None);
+        }
+        KeyBindEvent::UnhideItems => {
+            app.unhide_items(None);
+        }
+        KeyBindEvent::ReloadConfig => {
+            if let Err(e) = app.reload_config() {
+                log::warn!("Failed to reload config: {}", e);
             }
         }
         _ => {}
@@ -58,14 +313,55 @@ fn get_pos(mouse_event: MouseEvent) -> Position {
 
 async fn handle_click(mouse_event: MouseEvent, app: &App) -> Option<()> {
     let pos = get_pos(mouse_event);
-    let mut tree_state = &mut app.get_focused_tree_state_mut()?;
-    let ids = tree_state.rendered_at(pos)?.to_vec();
-    app.activate_menu_item(&ids, &mut tree_state).await?;
+    let ids = app.get_focused_tree_state()?.rendered_at(pos)?.to_vec();
+    app.activate_menu_item(&ids).await?;
     None
 }
 
-fn handle_scroll(mouse_event: MouseEvent, app: &mut App) -> Option<()> {
+async fn handle_item_click(
+    mouse_event: MouseEvent,
+    app: &mut App,
+    activation: ItemActivation,
+) -> Option<()> {
     let pos = get_pos(mouse_event);
+    let key = app.get_focused_sni_key_by_position(pos)?;
+    app.activate_item(&key, activation).await
+}
+
+/// Forwards the scroll to the item whose card border is under the mouse.
+async fn handle_item_scroll(mouse_event: MouseEvent, app: &App) -> Option<()> {
+    let pos = get_pos(mouse_event);
+    let (key, _) = app
+        .sni_states
+        .iter()
+        .find(|(_, v)| on_border(v.rect, pos))?;
+
+    let (delta, orientation) = scroll_step(mouse_event.kind, app.config.scroll_delta)?;
+    app.scroll_item(key, delta, orientation).await
+}
+
+/// Whether the position is on the border of the card.
+fn on_border(rect: Rect, pos: Position) -> bool {
+    rect.contains(pos) && !rect.inner(Margin::new(1, 1)).contains(pos)
+}
+
+/// Scroll sent to an item for a mouse wheel event, up and right are positive.
+fn scroll_step(kind: MouseEventKind, delta: i32) -> Option<(i32, ScrollOrientation)> {
+    match kind {
+        MouseEventKind::ScrollUp => Some((delta, ScrollOrientation::Vertical)),
+        MouseEventKind::ScrollDown => Some((-delta, ScrollOrientation::Vertical)),
+        MouseEventKind::ScrollLeft => Some((-delta, ScrollOrientation::Horizontal)),
+        MouseEventKind::ScrollRight => Some((delta, ScrollOrientation::Horizontal)),
+        _ => None,
+    }
+}
+
+async fn handle_scroll(mouse_event: MouseEvent, app: &mut App) -> Option<()> {
+    let pos = get_pos(mouse_event);
+
+    if app.config.item_scroll && handle_item_scroll(mouse_event, app).await.is_some() {
+        return Some(());
+    }
 
     // If mouse is over focused item, scroll its menu
     if let Some(sni_state) = app.get_focused_sni_state() {
@@ -101,9 +397,8 @@ async fn handle_move(mouse_event: MouseEvent, app: &mut App) -> Option<()> {
     let pos = get_pos(mouse_event);
     if let Some((_, sni_state)) = app.sni_states.get_index_mut(app.focused_sni_index) {
         if sni_state.rect.contains(pos) {
-            let mut tree_state = app.get_focused_tree_state_mut()?;
-            let rendered = tree_state.rendered_at(pos)?.to_owned();
-            tree_state.select(rendered.to_vec());
+            let rendered = app.get_focused_tree_state()?.rendered_at(pos)?.to_owned();
+            app.select_menu_item(rendered);
             return None;
         } else {
             sni_state.set_focused(false);
@@ -125,15 +420,59 @@ pub async fn handle_mouse_event(mouse_event: MouseEvent, app: &mut App) -> AppRe
         MouseEventKind::Down(MouseButton::Left) => {
             let _ = handle_click(mouse_event, app).await;
         }
-        MouseEventKind::Down(MouseButton::Right) => {}
-        MouseEventKind::Down(MouseButton::Middle) => {}
+        MouseEventKind::Down(MouseButton::Right) => {
+            let _ = handle_item_click(mouse_event, app, ItemActivation::ContextMenu).await;
+        }
+        MouseEventKind::Down(MouseButton::Middle) => {
+            let _ = handle_item_click(mouse_event, app, ItemActivation::Secondary).await;
+        }
         MouseEventKind::Moved => {
             let _ = handle_move(mouse_event, app).await;
         }
-        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
-            let _ = handle_scroll(mouse_event, app);
+        MouseEventKind::ScrollUp
+        | MouseEventKind::ScrollDown
+        | MouseEventKind::ScrollLeft
+        | MouseEventKind::ScrollRight => {
+            let _ = handle_scroll(mouse_event, app).await;
         }
         _ => {}
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wheel_up_and_right_scroll_forward() {
+        assert_eq!(
+            scroll_step(MouseEventKind::ScrollUp, 3),
+            Some((3, ScrollOrientation::Vertical))
+        );
+        assert_eq!(
+            scroll_step(MouseEventKind::ScrollDown, 3),
+            Some((-3, ScrollOrientation::Vertical))
+        );
+        assert_eq!(
+            scroll_step(MouseEventKind::ScrollRight, 1),
+            Some((1, ScrollOrientation::Horizontal))
+        );
+        assert_eq!(
+            scroll_step(MouseEventKind::ScrollLeft, 1),
+            Some((-1, ScrollOrientation::Horizontal))
+        );
+        assert_eq!(scroll_step(MouseEventKind::Moved, 1), None);
+    }
+
+    #[test]
+    fn only_the_card_border_scrolls_the_item() {
+        let rect = Rect::new(10, 5, 20, 6);
+        assert!(on_border(rect, Position::new(10, 5)));
+        assert!(on_border(rect, Position::new(15, 10)));
+        assert!(on_border(rect, Position::new(29, 7)));
+        assert!(!on_border(rect, Position::new(15, 7)));
+        assert!(!on_border(rect, Position::new(30, 7)));
+        assert!(!on_border(rect, Position::new(9, 5)));
+    }
+}
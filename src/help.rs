@@ -0,0 +1,116 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::config::{Config, KeyBindEvent};
+
+/// What the mouse does when mouse support is enabled.
+const MOUSE: [(&str, &str); 6] = [
+    (
+        "Move",
+        "Focus the card and select the menu entry under the pointer",
+    ),
+    ("Left click", "Activate the menu entry"),
+    ("Right click", "Open the item's context menu"),
+    ("Middle click", "Secondary activation of the item"),
+    ("Scroll", "Scroll the menu under the pointer, or the cards"),
+    (
+        "Scroll on border",
+        "Scroll the item itself, with item_scroll enabled",
+    ),
+];
+
+/// Overlay listing the effective key bindings of every action.
+pub struct HelpView<'a> {
+    /// lines scrolled past, clamped to the last page when rendering
+    pub scroll: &'a mut u16,
+    pub config: &'a Config,
+}
+
+impl HelpView<'_> {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let heading = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        let key = Style::default()
+            .fg(self.config.colors.border_fg_focused)
+            .add_modifier(Modifier::BOLD);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+
+        let bindings: Vec<(KeyBindEvent, String)> = KeyBindEvent::ALL
+            .into_iter()
+            .map(|event| (event, self.config.bindings(event).join(", ")))
+            .collect();
+        let width = bindings
+            .iter()
+            .map(|(_, keys)| keys.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(MOUSE.iter().map(|(m, _)| m.len()).max().unwrap_or(0));
+
+        let mut lines = vec![Line::styled("Keys", heading)];
+        for (event, keys) in bindings {
+            let keys = match keys.is_empty() {
+                true => Span::styled(format!("{:width$}", "unbound"), dim),
+                false => Span::styled(format!("{keys:width$}"), key),
+            };
+            lines.push(Line::from(vec![
+                keys,
+                Span::raw("  "),
+                Span::raw(event.description()),
+                Span::styled(format!(" ({})", event.name()), dim),
+            ]));
+        }
+
+        if self.config.mouse {
+            lines.push(Line::default());
+            lines.push(Line::styled("Mouse", heading));
+            for (action, description) in MOUSE {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{action:width$}"), key),
+                    Span::raw("  "),
+                    Span::raw(description),
+                ]));
+            }
+        }
+
+        lines
+    }
+}
+
+impl Widget for HelpView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let colors = &self.config.colors;
+        let block = Block::bordered()
+            .title("Help")
+            .title_bottom(" j/k scroll  any other key closes ")
+            .border_style(
+                Style::default()
+                    .fg(colors.border_fg_focused)
+                    .bg(colors.border_bg_focused),
+            );
+
+        let lines = self.lines();
+        let max_scroll = lines
+            .len()
+            .saturating_sub(block.inner(area).height as usize);
+        *self.scroll = (*self.scroll).min(max_scroll as u16);
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .wrap(Wrap { trim: false })
+            .scroll((*self.scroll, 0))
+            .block(block)
+            .render(area, buf);
+    }
+}
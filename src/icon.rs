@@ -0,0 +1,517 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+use system_tray::{
+    client::{Event, UpdateEvent},
+    item::{IconPixmap, Status, StatusNotifierItem},
+};
+
+use crate::{
+    graphics::{cell_size, Protocol},
+    icon_theme::IconThemes,
+};
+
+/// Width in cells of the icon drawn in card titles.
+pub const ICON_WIDTH: u16 = 2;
+
+/// Pixels with lower alpha are considered transparent when drawn with half blocks.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// RGBA image decoded from an icon pixmap or file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icon {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels row by row, non-premultiplied RGBA
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Icon {
+    /// Decodes the pixmap closest to `size`, preferring ones that are at least that large.
+    pub fn from_pixmaps(pixmaps: &[IconPixmap], size: u32) -> Option<Self> {
+        // Sizes come from the item, so they can't be trusted to fit together
+        let valid = |p: &&IconPixmap| {
+            p.width > 0
+                && p.height > 0
+                && (p.width as usize)
+                    .checked_mul(p.height as usize)
+                    .and_then(|len| len.checked_mul(4))
+                    == Some(p.pixels.len())
+        };
+        let pixmap = pixmaps
+            .iter()
+            .filter(valid)
+            .filter(|p| p.width as u32 >= size)
+            .min_by_key(|p| p.width)
+            .or_else(|| pixmaps.iter().filter(valid).max_by_key(|p| p.width))?;
+
+        // ARGB32 in network byte order
+        let pixels = pixmap
+            .pixels
+            .chunks_exact(4)
+            .map(|argb| [argb[1], argb[2], argb[3], argb[0]])
+            .collect();
+
+        Some(Self {
+            width: pixmap.width as u32,
+            height: pixmap.height as u32,
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG file.
+    pub fn from_png(path: &Path) -> Option<Self> {
+        let mut decoder = png::Decoder::new(File::open(path).ok()?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().ok()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).ok()?;
+        let bytes = &buf[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes
+                .chunks_exact(4)
+                .map(|p| [p[0], p[1], p[2], p[3]])
+                .collect(),
+            png::ColorType::Rgb => bytes
+                .chunks_exact(3)
+                .map(|p| [p[0], p[1], p[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => bytes
+                .chunks_exact(2)
+                .map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            png::ColorType::Grayscale => bytes.iter().map(|&p| [p, p, p, u8::MAX]).collect(),
+            png::ColorType::Indexed => return None,
+        };
+
+        Some(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Scales the icon by averaging the source pixels covered by each target pixel.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        let len = (width as usize).checked_mul(height as usize).unwrap_or(0);
+        let mut pixels = Vec::with_capacity(len);
+        for y in 0..height {
+            let (y0, y1) = span(y, height, self.height);
+            for x in 0..width {
+                let (x0, x1) = span(x, width, self.width);
+
+                // Average with premultiplied alpha, so transparent pixels don't darken edges
+                let mut sum = [0u64; 4];
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        let [r, g, b, a] = self.pixel(sx, sy).map(u64::from);
+                        sum[0] += r * a;
+                        sum[1] += g * a;
+                        sum[2] += b * a;
+                        sum[3] += a;
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as u64;
+                let pixel = match sum[3] {
+                    0 => [0; 4],
+                    alpha => [
+                        (sum[0] / alpha) as u8,
+                        (sum[1] / alpha) as u8,
+                        (sum[2] / alpha) as u8,
+                        (alpha / count) as u8,
+                    ],
+                };
+                pixels.push(pixel);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Draws `overlay` over the icon, scaled to the same size.
+    pub fn composite(&mut self, overlay: &Icon) {
+        let overlay = overlay.resize(self.width, self.height);
+        for (dst, src) in self.pixels.iter_mut().zip(overlay.pixels) {
+            let src_a = src[3] as u32;
+            let dst_a = dst[3] as u32 * (255 - src_a) / 255;
+            let out_a = src_a + dst_a;
+            if out_a == 0 {
+                continue;
+            }
+            for c in 0..3 {
+                dst[c] = ((src[c] as u32 * src_a + dst[c] as u32 * dst_a) / out_a) as u8;
+            }
+            dst[3] = out_a as u8;
+        }
+    }
+}
+
+/// Range of source pixels covered by the target pixel `i` out of `target`.
+fn span(i: u32, target: u32, source: u32) -> (u32, u32) {
+    let start = i * source / target;
+    let end = ((i + 1) * source / target).max(start + 1).min(source);
+    (start, end)
+}
+
+/// Draws an [Icon] with unicode half blocks, two pixels per cell.
+/// The icon is expected to be sized `area.width` x `area.height * 2`.
+pub struct HalfBlocks<'a>(pub &'a Icon);
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let icon = self.0;
+        let color = |[r, g, b, a]: [u8; 4]| (a >= ALPHA_THRESHOLD).then_some(Color::Rgb(r, g, b));
+
+        for y in 0..(area.height as u32).min(icon.height.div_ceil(2)) {
+            for x in 0..(area.width as u32).min(icon.width) {
+                let top = color(icon.pixel(x, y * 2));
+                let bottom = match y * 2 + 1 < icon.height {
+                    true => color(icon.pixel(x, y * 2 + 1)),
+                    false => None,
+                };
+                let Some(cell) = buf.cell_mut((area.x + x as u16, area.y + y as u16)) else {
+                    continue;
+                };
+
+                match (top, bottom) {
+                    (Some(top), Some(bottom)) => {
+                        cell.set_char('▀').set_fg(top).set_bg(bottom);
+                    }
+                    (Some(top), None) => {
+                        cell.set_char('▀').set_fg(top);
+                    }
+                    (None, Some(bottom)) => {
+                        cell.set_char('▄').set_fg(bottom);
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Loads and caches icons of tray items.
+#[derive(Debug, Default)]
+pub struct IconCache {
+    /// protocol icons are drawn with, icons aren't loaded without one
+    protocol: Option<Protocol>,
+    /// size of a terminal cell in pixels
+    cell_size: (u32, u32),
+    /// lookup of named icons in icon themes
+    themes: IconThemes,
+    /// decoded icon files by their path
+    files: RefCell<HashMap<PathBuf, Option<Rc<Icon>>>>,
+    /// icons resized for the card titles, by item key, kind and size
+    sized: RefCell<HashMap<SizedKey, Option<Rc<Icon>>>>,
+}
+
+/// Item key, which of its icons and the size in pixels it was resized to.
+type SizedKey = (String, IconKind, (u32, u32));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum IconKind {
+    Icon,
+    Attention,
+    Overlay,
+}
+
+impl IconCache {
+    pub fn new(protocol: Option<Protocol>, icon_theme: &str) -> Self {
+        Self {
+            protocol,
+            cell_size: cell_size(),
+            themes: IconThemes::new(icon_theme),
+            files: RefCell::default(),
+            sized: RefCell::default(),
+        }
+    }
+
+    pub fn protocol(&self) -> Option<Protocol> {
+        self.protocol
+    }
+
+    /// Queries the cell size again, it changes with the terminal font.
+    pub fn update_cell_size(&mut self) {
+        self.cell_size = cell_size();
+        self.sized.get_mut().clear();
+    }
+
+    /// Forgets the icons of an item once they change or the item is gone.
+    pub fn update(&mut self, event: &Event) {
+        let key = match event {
+            Event::Add(key, _)
+            | Event::Remove(key)
+            | Event::Update(
+                key,
+                UpdateEvent::Icon { .. }
+                | UpdateEvent::OverlayIcon(_)
+                | UpdateEvent::AttentionIcon(_)
+                | UpdateEvent::Status(_),
+            ) => key,
+            Event::Update(_, _) => return,
+        };
+        self.sized.get_mut().retain(|(item, _, _), _| item != key);
+    }
+
+    /// Size in pixels of the icons drawn in card titles.
+    fn icon_size(&self) -> (u32, u32) {
+        let (cell_width, cell_height) = self.cell_size;
+        match self.protocol {
+            // Half blocks fit two pixels in a cell vertically
+            Some(Protocol::Halfblocks) | None => (ICON_WIDTH as u32, 2),
+            Some(_) => (ICON_WIDTH as u32 * cell_width, cell_height),
+        }
+    }
+
+    fn load_file(&self, path: PathBuf) -> Option<Rc<Icon>> {
+        self.files
+            .borrow_mut()
+            .entry(path)
+            .or_insert_with_key(|path| Icon::from_png(path).map(Rc::new))
+            .clone()
+    }
+
+    /// Finds and decodes a named icon of about `size` pixels.
+    fn find_named(&self, name: &str, size: u32, theme_path: Option<&str>) -> Option<Rc<Icon>> {
+        if name.is_empty() {
+            return None;
+        }
+        self.load_file(self.themes.find(name, size, theme_path)?)
+    }
+
+    /// Loads the icon resized to `width` x `height` pixels, or takes it from the cache.
+    fn load(
+        &self,
+        key: &str,
+        kind: IconKind,
+        name: Option<&str>,
+        pixmaps: Option<&[IconPixmap]>,
+        theme_path: Option<&str>,
+        (width, height): (u32, u32),
+    ) -> Option<Rc<Icon>> {
+        let sized_key = (key.to_owned(), kind, (width, height));
+        if let Some(icon) = self.sized.borrow().get(&sized_key) {
+            return icon.clone();
+        }
+
+        // Icon names are preferred over pixmaps by the specification
+        let size = width.max(height);
+        let icon = match name.and_then(|name| self.find_named(name, size, theme_path)) {
+            Some(icon) => Some(icon.resize(width, height)),
+            None => pixmaps
+                .and_then(|pixmaps| Icon::from_pixmaps(pixmaps, size))
+                .map(|icon| icon.resize(width, height)),
+        }
+        .map(Rc::new);
+        self.sized.borrow_mut().insert(sized_key, icon.clone());
+        icon
+    }
+
+    /// Icon of the item with its overlay drawn on top, sized for the card title.
+    pub fn get(&self, key: &str, item: &StatusNotifierItem) -> Option<Icon> {
+        self.protocol?;
+        let size = self.icon_size();
+        let theme_path = item.icon_theme_path.as_deref();
+        let attention = match item.status {
+            Status::NeedsAttention => self.load(
+                key,
+                IconKind::Attention,
+                item.attention_icon_name.as_deref(),
+                item.attention_icon_pixmap.as_deref(),
+                theme_path,
+                size,
+            ),
+            _ => None,
+        };
+        let icon = match attention {
+            Some(icon) => icon,
+            None => self.load(
+                key,
+                IconKind::Icon,
+                item.icon_name.as_deref(),
+                item.icon_pixmap.as_deref(),
+                theme_path,
+                size,
+            )?,
+        };
+
+        let mut icon = Icon::clone(&icon);
+        let overlay = self.load(
+            key,
+            IconKind::Overlay,
+            item.overlay_icon_name.as_deref(),
+            item.overlay_icon_pixmap.as_deref(),
+            theme_path,
+            size,
+        );
+        if let Some(overlay) = overlay {
+            icon.composite(&overlay);
+        }
+
+        Some(icon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::item;
+
+    /// Square pixmap filled with one ARGB color.
+    fn pixmap(size: i32, argb: [u8; 4]) -> IconPixmap {
+        IconPixmap {
+            width: size,
+            height: size,
+            pixels: argb.repeat((size * size) as usize),
+        }
+    }
+
+    fn filled(width: u32, height: u32, rgba: [u8; 4]) -> Icon {
+        Icon {
+            width,
+            height,
+            pixels: vec![rgba; (width * height) as usize],
+        }
+    }
+
+    #[test]
+    fn pixmaps_are_converted_from_argb() {
+        let icon = Icon::from_pixmaps(&[pixmap(1, [255, 1, 2, 3])], 1).unwrap();
+        assert_eq!(icon.pixels, [[1, 2, 3, 255]]);
+    }
+
+    #[test]
+    fn smallest_pixmap_at_least_the_size_is_picked() {
+        let pixmaps = [
+            pixmap(64, [255; 4]),
+            pixmap(16, [255; 4]),
+            pixmap(32, [255; 4]),
+        ];
+        assert_eq!(Icon::from_pixmaps(&pixmaps, 20).unwrap().width, 32);
+        assert_eq!(Icon::from_pixmaps(&pixmaps, 100).unwrap().width, 64);
+    }
+
+    #[test]
+    fn pixmaps_with_the_wrong_length_are_skipped() {
+        let broken = IconPixmap {
+            width: 32,
+            height: 32,
+            pixels: vec![255; 4],
+        };
+        let pixmaps = [broken, pixmap(8, [255; 4])];
+        assert_eq!(Icon::from_pixmaps(&pixmaps[..1], 16), None);
+        assert_eq!(Icon::from_pixmaps(&pixmaps, 16).unwrap().width, 8);
+    }
+
+    #[test]
+    fn resize_averages_covered_pixels() {
+        let icon = Icon {
+            width: 2,
+            height: 1,
+            pixels: vec![[200, 0, 0, 255], [0, 100, 0, 255]],
+        };
+        assert_eq!(icon.resize(1, 1).pixels, [[100, 50, 0, 255]]);
+        assert_eq!(icon.resize(4, 2).pixels.len(), 8);
+    }
+
+    #[test]
+    fn transparent_pixels_do_not_darken_resized_icons() {
+        let icon = Icon {
+            width: 2,
+            height: 1,
+            pixels: vec![[200, 100, 0, 255], [0, 0, 0, 0]],
+        };
+        assert_eq!(icon.resize(1, 1).pixels, [[200, 100, 0, 127]]);
+    }
+
+    #[test]
+    fn overlay_is_drawn_over_the_icon() {
+        let mut icon = filled(2, 2, [0, 0, 255, 255]);
+        icon.composite(&filled(1, 1, [0, 0, 0, 0]));
+        assert_eq!(icon, filled(2, 2, [0, 0, 255, 255]));
+        icon.composite(&filled(1, 1, [255, 0, 0, 255]));
+        assert_eq!(icon, filled(2, 2, [255, 0, 0, 255]));
+    }
+
+    fn cache() -> IconCache {
+        IconCache {
+            protocol: Some(Protocol::Halfblocks),
+            ..IconCache::default()
+        }
+    }
+
+    fn with_pixmap(argb: [u8; 4]) -> StatusNotifierItem {
+        StatusNotifierItem {
+            icon_pixmap: Some(vec![pixmap(2, argb)]),
+            ..item("steam")
+        }
+    }
+
+    fn icon_changed() -> UpdateEvent {
+        UpdateEvent::Icon {
+            icon_name: None,
+            icon_pixmap: None,
+        }
+    }
+
+    #[test]
+    fn sized_icons_are_cached_until_the_icon_changes() {
+        let mut cache = cache();
+        let red = cache.get(":1.42", &with_pixmap([255, 255, 0, 0])).unwrap();
+        assert_eq!(red, filled(2, 2, [255, 0, 0, 255]));
+
+        let green = with_pixmap([255, 0, 255, 0]);
+        let title = UpdateEvent::Title(None);
+        cache.update(&Event::Update(String::from(":1.42"), title));
+        assert_eq!(cache.get(":1.42", &green).unwrap(), red);
+
+        cache.update(&Event::Update(String::from(":1.42"), icon_changed()));
+        assert_eq!(
+            cache.get(":1.42", &green),
+            Some(filled(2, 2, [0, 255, 0, 255]))
+        );
+    }
+
+    #[test]
+    fn icons_of_other_items_are_kept() {
+        let mut cache = cache();
+        cache.get(":1.42", &with_pixmap([255; 4]));
+        cache.get(":1.43", &with_pixmap([255; 4]));
+        cache.update(&Event::Update(String::from(":1.43"), icon_changed()));
+        assert!(cache
+            .sized
+            .borrow()
+            .keys()
+            .all(|(key, _, _)| key == ":1.42"));
+    }
+
+    #[test]
+    fn removed_items_drop_their_icons() {
+        let mut cache = cache();
+        cache.get(":1.42", &with_pixmap([255; 4]));
+        assert!(!cache.sized.borrow().is_empty());
+        cache.update(&Event::Remove(String::from(":1.42")));
+        assert!(cache.sized.borrow().is_empty());
+    }
+
+    #[test]
+    fn no_icons_without_a_protocol() {
+        let cache = IconCache::default();
+        assert_eq!(cache.get(":1.42", &with_pixmap([255; 4])), None);
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Only PNG icons can be decoded.
+const EXTENSION: &str = "png";
+
+/// Theme every other theme falls back to.
+const FALLBACK_THEME: &str = "hicolor";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// Subdirectory of a theme holding icons of one size.
+#[derive(Debug, Clone)]
+struct Directory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl Directory {
+    fn matches_size(&self, size: u32) -> bool {
+        if self.scale != 1 {
+            return false;
+        }
+        match self.kind {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryType::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    fn size_distance(&self, size: u32) -> u32 {
+        let (min, max) = match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if size < min {
+            min - size
+        } else {
+            size.saturating_sub(max)
+        }
+    }
+}
+
+/// Parsed `index.theme` of an icon theme.
+#[derive(Debug, Clone, Default)]
+struct Theme {
+    inherits: Vec<String>,
+    directories: Vec<Directory>,
+}
+
+impl Theme {
+    fn parse(index: &str) -> Self {
+        let mut sections: HashMap<&str, HashMap<&str, &str>> = HashMap::new();
+        let mut current = None;
+        for line in index.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(name);
+                continue;
+            }
+            if let (Some(section), Some((key, value))) = (current, line.split_once('=')) {
+                sections
+                    .entry(section)
+                    .or_default()
+                    .insert(key.trim(), value.trim());
+            }
+        }
+
+        let list = |value: Option<&&str>| -> Vec<String> {
+            value
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let header = sections.get("Icon Theme").cloned().unwrap_or_default();
+
+        let directories = list(header.get("Directories"))
+            .into_iter()
+            .chain(list(header.get("ScaledDirectories")))
+            .filter_map(|path| {
+                let section = sections.get(path.as_str())?;
+                let number = |key: &str| section.get(key).and_then(|v| v.parse::<u32>().ok());
+                let size = number("Size")?;
+                let kind = match section.get("Type").copied() {
+                    Some("Fixed") => DirectoryType::Fixed,
+                    Some("Scalable") => DirectoryType::Scalable,
+                    _ => DirectoryType::Threshold,
+                };
+                Some(Directory {
+                    size,
+                    scale: number("Scale").unwrap_or(1),
+                    kind,
+                    min_size: number("MinSize").unwrap_or(size),
+                    max_size: number("MaxSize").unwrap_or(size),
+                    threshold: number("Threshold").unwrap_or(2),
+                    path,
+                })
+            })
+            .collect();
+
+        Self {
+            inherits: list(header.get("Inherits")),
+            directories,
+        }
+    }
+}
+
+/// Icon name, size and extra theme path of a lookup.
+type LookupKey = (String, u32, Option<String>);
+
+/// Icon lookup following the freedesktop Icon Theme specification.
+///
+/// Themes and looked up paths are cached, so repeated lookups don't touch the filesystem.
+#[derive(Debug, Default)]
+pub struct IconThemes {
+    /// name of the preferred theme
+    theme: String,
+    /// directories searched for themes and icons, in order of preference
+    base_dirs: Vec<PathBuf>,
+    /// parsed themes by name, `None` when the theme isn't installed
+    themes: RefCell<HashMap<String, Option<Theme>>>,
+    /// resolved icon paths by name, size and extra theme path
+    lookups: RefCell<HashMap<LookupKey, Option<PathBuf>>>,
+}
+
+impl IconThemes {
+    pub fn new(theme: &str) -> Self {
+        Self {
+            theme: theme.to_owned(),
+            base_dirs: base_dirs(),
+            themes: RefCell::default(),
+            lookups: RefCell::default(),
+        }
+    }
+
+    /// Finds the file of the icon closest to `size` pixels.
+    ///
+    /// `theme_path` is an extra directory the item ships its icons in,
+    /// searched before any other.
+    pub fn find(&self, name: &str, size: u32, theme_path: Option<&str>) -> Option<PathBuf> {
+        let key = (name.to_owned(), size, theme_path.map(String::from));
+        if let Some(result) = self.lookups.borrow().get(&key) {
+            return result.clone();
+        }
+
+        let result = self.lookup(name, size, theme_path);
+        log::debug!("Icon {} looked up as {:?}", name, result);
+        self.lookups.borrow_mut().insert(key, result.clone());
+        result
+    }
+
+    fn lookup(&self, name: &str, size: u32, theme_path: Option<&str>) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.exists().then(|| path.to_path_buf());
+        }
+
+        let extra_dir = theme_path.filter(|p| !p.is_empty()).map(PathBuf::from);
+        let mut base_dirs: Vec<&Path> = Vec::new();
+        base_dirs.extend(extra_dir.as_deref());
+        base_dirs.extend(self.base_dirs.iter().map(PathBuf::as_path));
+
+        // Apps often put their icons straight in their theme path
+        if let Some(file) = extra_dir.as_ref().and_then(|dir| existing(dir.join(name))) {
+            return Some(file);
+        }
+
+        let mut visited = Vec::new();
+        self.lookup_in_theme(&self.theme, name, size, &base_dirs, &mut visited)
+            .or_else(|| self.lookup_in_theme(FALLBACK_THEME, name, size, &base_dirs, &mut visited))
+            .or_else(|| {
+                // Unthemed icons, e.g. /usr/share/pixmaps
+                base_dirs
+                    .iter()
+                    .chain(&[Path::new("/usr/share/pixmaps")])
+                    .find_map(|dir| existing(dir.join(name)))
+            })
+    }
+
+    fn lookup_in_theme(
+        &self,
+        theme_name: &str,
+        name: &str,
+        size: u32,
+        base_dirs: &[&Path],
+        visited: &mut Vec<String>,
+    ) -> Option<PathBuf> {
+        if visited.iter().any(|v| v == theme_name) {
+            return None;
+        }
+        visited.push(theme_name.to_owned());
+
+        let theme = self.theme(theme_name, base_dirs)?;
+        let candidates = |dir: &Directory| {
+            base_dirs
+                .iter()
+                .map(|base| base.join(theme_name).join(&dir.path))
+                .collect::<Vec<_>>()
+        };
+
+        // Exact size match first, otherwise the closest size of this theme
+        for dir in theme.directories.iter().filter(|d| d.matches_size(size)) {
+            if let Some(file) = candidates(dir)
+                .into_iter()
+                .find_map(|d| existing(d.join(name)))
+            {
+                return Some(file);
+            }
+        }
+        let closest = theme
+            .directories
+            .iter()
+            .filter_map(|dir| {
+                let file = candidates(dir)
+                    .into_iter()
+                    .find_map(|d| existing(d.join(name)))?;
+                // Prefer larger icons on ties, scaling down looks better
+                Some(((dir.size_distance(size), u32::MAX - dir.size), file))
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, file)| file);
+        if closest.is_some() {
+            return closest;
+        }
+
+        theme
+            .inherits
+            .iter()
+            .find_map(|parent| self.lookup_in_theme(parent, name, size, base_dirs, visited))
+    }
+
+    fn theme(&self, name: &str, base_dirs: &[&Path]) -> Option<Theme> {
+        if let Some(theme) = self.themes.borrow().get(name) {
+            return theme.clone();
+        }
+
+        let theme = base_dirs
+            .iter()
+            .find_map(|dir| fs::read_to_string(dir.join(name).join("index.theme")).ok())
+            .map(|index| Theme::parse(&index));
+        self.themes
+            .borrow_mut()
+            .insert(name.to_owned(), theme.clone());
+        theme
+    }
+}
+
+/// `dir/name.png` if it exists.
+fn existing(path: PathBuf) -> Option<PathBuf> {
+    let mut file = path.into_os_string();
+    file.push(".");
+    file.push(EXTENSION);
+    let file = PathBuf::from(file);
+    file.is_file().then_some(file)
+}
+
+/// Base directories of icon themes, in order of preference.
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(data) = dirs::data_dir() {
+        dirs.push(data.join("icons"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".icons"));
+    }
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or(String::from("/usr/local/share:/usr/share"));
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|d| !d.is_empty())
+            .map(|d| Path::new(d).join("icons")),
+    );
+
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = "
+[Icon Theme]
+Name=Test
+Inherits=Adwaita, hicolor,
+Directories=16x16/apps,scalable/apps,48x48/apps,missing
+ScaledDirectories=16x16@2/apps
+
+# threshold directory with the default threshold
+[16x16/apps]
+Size=16
+
+[16x16@2/apps]
+Size=16
+Scale=2
+Type=Fixed
+
+[scalable/apps]
+Size = 64
+Type = Scalable
+MinSize = 8
+MaxSize = 512
+
+[48x48/apps]
+Type=Fixed
+";
+
+    #[test]
+    fn theme_reads_inherits_and_directories() {
+        let theme = Theme::parse(INDEX);
+        assert_eq!(theme.inherits, ["Adwaita", "hicolor"]);
+
+        // directories without a section or a size are skipped
+        let paths: Vec<&str> = theme.directories.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, ["16x16/apps", "scalable/apps", "16x16@2/apps"]);
+
+        let threshold = &theme.directories[0];
+        assert_eq!(threshold.kind, DirectoryType::Threshold);
+        assert_eq!(
+            (threshold.size, threshold.scale, threshold.threshold),
+            (16, 1, 2)
+        );
+        assert!(threshold.matches_size(18) && !threshold.matches_size(19));
+
+        let scalable = &theme.directories[1];
+        assert_eq!(scalable.kind, DirectoryType::Scalable);
+        assert_eq!((scalable.min_size, scalable.max_size), (8, 512));
+        assert!(scalable.matches_size(8) && scalable.matches_size(512));
+
+        let scaled = &theme.directories[2];
+        assert_eq!((scaled.kind, scaled.scale), (DirectoryType::Fixed, 2));
+        assert!(!scaled.matches_size(16));
+    }
+
+    #[test]
+    fn theme_without_header_is_empty() {
+        let theme = Theme::parse("[16x16/apps]\nSize=16\n");
+        assert!(theme.inherits.is_empty());
+        assert!(theme.directories.is_empty());
+        assert!(Theme::parse("").directories.is_empty());
+    }
+}
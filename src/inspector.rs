@@ -0,0 +1,314 @@
+use std::io::{self, Write};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListState, StatefulWidget, Widget},
+};
+use system_tray::{
+    item::{IconPixmap, StatusNotifierItem},
+    menu::{MenuItem, ToggleType, TrayMenu},
+};
+
+use crate::{alert::write_osc, config::Config, graphics::base64};
+
+/// State of the inspector overlay showing the raw properties of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspector {
+    /// key of the inspected item
+    pub key: String,
+    /// index of the selected field
+    pub selected: usize,
+}
+
+impl Inspector {
+    pub fn new(key: String) -> Self {
+        Self { key, selected: 0 }
+    }
+}
+
+/// A row of the inspector, its value is what gets copied.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+}
+
+impl Field {
+    fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+fn optional(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| String::from("-"))
+}
+
+fn pixmap_sizes(pixmaps: &Option<Vec<IconPixmap>>) -> String {
+    match pixmaps.as_deref() {
+        Some(pixmaps) if !pixmaps.is_empty() => pixmaps
+            .iter()
+            .map(|p| format!("{}x{}", p.width, p.height))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => String::from("-"),
+    }
+}
+
+/// Every property of the item followed by its dbusmenu tree.
+pub fn fields(key: &str, (item, menu): &(StatusNotifierItem, Option<TrayMenu>)) -> Vec<Field> {
+    let (bus, path) = key.split_once('/').unwrap_or((key, ""));
+    let mut fields = vec![
+        Field::new("Address", key),
+        Field::new("Bus name", bus),
+        Field::new("Object path", format!("/{path}")),
+        Field::new("Id", &item.id),
+        Field::new("Title", optional(&item.title)),
+        Field::new("Category", format!("{:?}", item.category)),
+        Field::new("Status", format!("{:?}", item.status)),
+        Field::new("Window id", item.window_id.to_string()),
+        Field::new("Icon name", optional(&item.icon_name)),
+        Field::new("Icon pixmaps", pixmap_sizes(&item.icon_pixmap)),
+        Field::new("Icon theme path", optional(&item.icon_theme_path)),
+        Field::new("Overlay icon name", optional(&item.overlay_icon_name)),
+        Field::new("Attention icon name", optional(&item.attention_icon_name)),
+        Field::new("Attention movie name", optional(&item.attention_movie_name)),
+        Field::new("Item is menu", item.item_is_menu.to_string()),
+        Field::new("Menu path", optional(&item.menu)),
+    ];
+
+    match &item.tool_tip {
+        Some(tooltip) => {
+            fields.push(Field::new("Tooltip title", &tooltip.title));
+            fields.push(Field::new("Tooltip description", &tooltip.description));
+            fields.push(Field::new("Tooltip icon name", &tooltip.icon_name));
+        }
+        None => fields.push(Field::new("Tooltip", "-")),
+    }
+
+    match menu {
+        Some(menu) => {
+            fields.push(Field::new("Menu", format!("revision {}", menu.id)));
+            menu_fields(&menu.submenus, 1, &mut fields);
+        }
+        None => fields.push(Field::new("Menu", "-")),
+    }
+
+    fields
+}
+
+/// One field per menu entry, indented by depth and named by id.
+fn menu_fields(items: &[MenuItem], depth: usize, fields: &mut Vec<Field>) {
+    for item in items {
+        let mut value = format!("{:?}", item.menu_type);
+        if let Some(label) = &item.label {
+            value.push_str(&format!(" {label:?}"));
+        }
+        if !item.enabled {
+            value.push_str(" disabled");
+        }
+        if !item.visible {
+            value.push_str(" hidden");
+        }
+        if item.toggle_type != ToggleType::CannotBeToggled {
+            value.push_str(&format!(" {:?}={:?}", item.toggle_type, item.toggle_state));
+        }
+        if let Some(display) = &item.children_display {
+            value.push_str(&format!(" children-display={display}"));
+        }
+        if let Some(icon) = &item.icon_name {
+            value.push_str(&format!(" icon={icon}"));
+        }
+
+        fields.push(Field::new(
+            format!("{}{}", "  ".repeat(depth), item.id),
+            value,
+        ));
+        menu_fields(&item.submenu, depth + 1, fields);
+    }
+}
+
+/// Shows control characters like newlines escaped, so a value stays on its line.
+/// The field keeps the raw value for copying.
+fn escape_controls(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c.is_control() {
+            true => c.escape_debug().to_string(),
+            false => c.to_string(),
+        })
+        .collect()
+}
+
+/// Copies the text to the system clipboard with the OSC 52 escape sequence.
+pub fn copy_to_clipboard(out: &mut impl Write, text: &str) -> io::Result<()> {
+    write_osc(out, &["52", "c", &base64(text.as_bytes())])?;
+    out.flush()
+}
+
+/// Overlay listing the fields of the inspected item.
+pub struct InspectorView<'a> {
+    pub fields: &'a [Field],
+    pub selected: usize,
+    pub config: &'a Config,
+}
+
+impl Widget for InspectorView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(90)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let colors = &self.config.colors;
+        let name_width = self
+            .fields
+            .iter()
+            .map(|f| f.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = self
+            .fields
+            .iter()
+            .map(|field| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:width$}  ", field.name, width = name_width),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(escape_controls(&field.value)),
+                ])
+            })
+            .collect();
+
+        let block = Block::bordered()
+            .title("Inspector")
+            .title_bottom(" y copy  esc close ")
+            .border_style(
+                Style::default()
+                    .fg(colors.border_fg_focused)
+                    .bg(colors.border_bg_focused),
+            );
+        let list = List::new(lines)
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .highlight_style(
+                Style::default()
+                    .fg(colors.fg_highlighted)
+                    .bg(colors.bg_highlighted),
+            )
+            .block(block);
+
+        Clear.render(area, buf);
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, item, submenu, with_menu};
+    use system_tray::{
+        item::Tooltip,
+        menu::{MenuType, ToggleState},
+    };
+
+    #[test]
+    fn control_characters_are_escaped() {
+        assert_eq!(
+            escape_controls("Battery\n80%\tcharging"),
+            "Battery\\n80%\\tcharging"
+        );
+        assert_eq!(escape_controls("\x1b[31mred\r"), "\\u{1b}[31mred\\r");
+        assert_eq!(
+            escape_controls("Wi-Fi › home \"5G\""),
+            "Wi-Fi › home \"5G\""
+        );
+    }
+
+    fn value<'a>(fields: &'a [Field], name: &str) -> Option<&'a str> {
+        let field = fields.iter().find(|field| field.name == name)?;
+        Some(&field.value)
+    }
+
+    #[test]
+    fn address_is_split_into_bus_and_path() {
+        let fields = fields(":1.42/StatusNotifierItem", &(item("steam"), None));
+        assert_eq!(value(&fields, "Bus name"), Some(":1.42"));
+        assert_eq!(value(&fields, "Object path"), Some("/StatusNotifierItem"));
+        assert_eq!(value(&fields, "Title"), Some("-"));
+        assert_eq!(value(&fields, "Tooltip"), Some("-"));
+        assert_eq!(value(&fields, "Menu"), Some("-"));
+    }
+
+    #[test]
+    fn tooltip_is_listed_field_by_field() {
+        let item = StatusNotifierItem {
+            tool_tip: Some(Tooltip {
+                icon_name: String::from("network-wireless"),
+                icon_data: Vec::new(),
+                title: String::from("Wi-Fi"),
+                description: String::from("Connected to home"),
+            }),
+            ..item("nm-applet")
+        };
+        let fields = fields(":1.42", &(item, None));
+        assert_eq!(value(&fields, "Tooltip title"), Some("Wi-Fi"));
+        assert_eq!(
+            value(&fields, "Tooltip description"),
+            Some("Connected to home")
+        );
+        assert_eq!(
+            value(&fields, "Tooltip icon name"),
+            Some("network-wireless")
+        );
+        assert_eq!(value(&fields, "Tooltip"), None);
+    }
+
+    #[test]
+    fn menu_entries_are_indented_by_depth() {
+        let mute = MenuItem {
+            toggle_type: ToggleType::Checkmark,
+            toggle_state: ToggleState::On,
+            enabled: false,
+            ..entry(3, "Mute")
+        };
+        let pair = with_menu(item("pasystray"), vec![submenu(2, "Output", vec![mute])]);
+        let fields = fields(":1.42", &pair);
+        assert_eq!(value(&fields, "Menu"), Some("revision 1"));
+        assert_eq!(
+            value(&fields, "  2"),
+            Some("Standard \"Output\" children-display=submenu")
+        );
+        assert_eq!(
+            value(&fields, "    3"),
+            Some("Standard \"Mute\" disabled Checkmark=On")
+        );
+    }
+
+    #[test]
+    fn separators_and_hidden_entries_are_listed() {
+        let hidden = MenuItem {
+            visible: false,
+            menu_type: MenuType::Separator,
+            ..MenuItem::default()
+        };
+        let pair = with_menu(item("steam"), vec![hidden]);
+        let fields = fields(":1.42", &pair);
+        assert_eq!(value(&fields, "  0"), Some("Separator disabled hidden"));
+    }
+
+    #[test]
+    fn copy_uses_osc_52() {
+        let mut out = Vec::new();
+        copy_to_clipboard(&mut out, "hi").unwrap();
+        assert_eq!(out, b"\x1b]52;c;aGk=\x07");
+    }
+}
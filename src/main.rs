@@ -1,6 +1,7 @@
 use std::{fs::File, io};
 
 use crate::{
+    alert::Alerter,
     app::{App, AppResult},
     cli::Cli,
     config::Config,
@@ -16,11 +17,26 @@ use simplelog::{CombinedLogger, Config as Conf, LevelFilter, WriteLogger};
 
 use system_tray::{client::Client, item::StatusNotifierItem, menu::TrayMenu};
 
+pub mod alert;
 pub mod app;
 pub mod cli;
+pub mod commands;
 pub mod config;
+pub mod dbus;
+pub mod entries;
 pub mod event;
+#[cfg(test)]
+mod fixtures;
+pub mod graphics;
 pub mod handler;
+pub mod help;
+pub mod icon;
+pub mod icon_theme;
+pub mod inspector;
+pub mod markup;
+pub mod palette;
+pub mod pattern;
+pub mod search;
 pub mod tui;
 pub mod ui;
 pub mod wrappers;
@@ -49,9 +65,18 @@ async fn main() -> AppResult<()> {
 
     let config = Config::new(&cli.config_path)?;
 
+    if let Some(command) = cli.command {
+        if let Err(e) = commands::run(command, config).await {
+            eprintln!("{CMD}: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let client = Client::new().await.unwrap();
     log::info!("Client is initialized");
     let mut tray_rx = client.subscribe();
+    let connection = zbus::Connection::session().await?;
 
     log::info!(
         "status: {}, traymenu {}, client {}",
@@ -61,38 +86,48 @@ async fn main() -> AppResult<()> {
     );
 
     // Create an application.
-    let mut app = App::new(client, config);
-    let map = app.config.key_map.clone();
+    let mut app = App::new(client, connection, config);
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
-    let events = EventHandler::new(app.config.mouse, map);
+    let events = EventHandler::new();
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
     log::info!("Initialized TUI");
 
     tui.draw(&mut app)?;
 
+    let mut alerter = Alerter::new(&app);
     while app.running {
         tui.draw(&mut app)?;
         tokio::select! {
             Ok(update) = tray_rx.recv() => {
                 log::debug!("{}", LoggableEvent(&update));
                 app.update();
+                app.icons.update(&update);
                 if let system_tray::client::Event::Remove(_) = update {
                     app.sync_focus();
                 }
+                if let Err(e) = alerter.update(&app).await {
+                    log::warn!("Failed to alert: {}", e);
+                }
             }
 
             Ok(event) = tui.events.next() => {
                 log::debug!("Key event: {:?}", &event);
                 match event {
                     Event::Key(key_event) => handle_key_events(key_event, &mut app).await?,
-                    Event::Mouse(mouse_event) => {
+                    // Mouse capture stays enabled, so mouse support can be toggled
+                    Event::Mouse(mouse_event) if app.config.mouse => {
                         handle_mouse_event(mouse_event, &mut app).await?
                     },
-                    Event::Resize(_, _) => {tui.draw(&mut app).unwrap()}
+                    Event::Mouse(_) => {}
+                    Event::Resize(_, _) => {
+                        app.icons.update_cell_size();
+                        app.images.invalidate();
+                        tui.draw(&mut app).unwrap()
+                    }
                     Event::FocusLost => {
                         // doensn't work for some reason
                     }
@@ -102,6 +137,8 @@ async fn main() -> AppResult<()> {
     }
 
     log::info!("Exiting application");
+    app.images.clear(&mut io::stdout())?;
+    alerter.restore_title(&mut io::stdout())?;
     tui.exit()?;
     Ok(())
 }
@@ -0,0 +1,233 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Converts the HTML-like markup allowed in StatusNotifierItem tooltips into styled lines.
+///
+/// `<b>`, `<i>`, `<u>`, `<s>`, `<a>` and line breaks are styled, other tags are dropped.
+pub fn markup_to_lines(markup: &str) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::default()];
+    // Styles of the open tags, the innermost last
+    let mut stack: Vec<(String, Style)> = Vec::new();
+    let mut rest = markup;
+
+    while !rest.is_empty() {
+        let style = stack.last().map_or(Style::default(), |(_, style)| *style);
+
+        let Some(start) = rest.find('<') else {
+            push_text(&mut lines, rest, style);
+            break;
+        };
+        push_text(&mut lines, &rest[..start], style);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('>') else {
+            // Not a tag, just a lone `<`
+            push_text(&mut lines, rest, style);
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match name.as_str() {
+            "br" => lines.push(Line::default()),
+            _ if closing => {
+                if let Some(i) = stack.iter().rposition(|(open, _)| *open == name) {
+                    stack.truncate(i);
+                }
+                if name == "p" || name == "div" {
+                    lines.push(Line::default());
+                }
+            }
+            _ => {
+                let style = match name.as_str() {
+                    "b" | "strong" => style.add_modifier(Modifier::BOLD),
+                    "i" | "em" => style.add_modifier(Modifier::ITALIC),
+                    "u" => style.add_modifier(Modifier::UNDERLINED),
+                    "s" | "strike" | "del" => style.add_modifier(Modifier::CROSSED_OUT),
+                    "a" => style.fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+                    _ => style,
+                };
+                // Self-closing tags like `<img/>` don't style anything
+                if !tag.ends_with('/') {
+                    stack.push((name, style));
+                }
+            }
+        }
+    }
+
+    // Drop empty lines left by trailing breaks
+    while lines.len() > 1 && lines.last().is_some_and(|line| line.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Adds text to the last line, starting new lines at newlines.
+fn push_text(lines: &mut Vec<Line<'static>>, text: &str, style: Style) {
+    for (i, part) in text.split('\n').enumerate() {
+        if i > 0 {
+            lines.push(Line::default());
+        }
+        if !part.is_empty() {
+            if let Some(line) = lines.last_mut() {
+                line.push_span(Span::styled(unescape(part), style));
+            }
+        }
+    }
+}
+
+/// Replaces character references with the characters they stand for.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let ch = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => name.strip_prefix('#')?.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (ch, entity) {
+            (Some(ch), Some((_, end))) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOLD: Style = Style::new().add_modifier(Modifier::BOLD);
+
+    /// Text and style of every span, line by line.
+    fn spans(markup: &str) -> Vec<Vec<(String, Style)>> {
+        markup_to_lines(markup)
+            .into_iter()
+            .map(|line| {
+                line.spans
+                    .into_iter()
+                    .map(|span| (span.content.into_owned(), span.style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn plain(text: &str) -> (String, Style) {
+        (text.to_owned(), Style::default())
+    }
+
+    #[test]
+    fn plain_text_is_one_line() {
+        assert_eq!(spans("Battery 80%"), [[plain("Battery 80%")]]);
+        assert_eq!(spans(""), [[]; 1]);
+    }
+
+    #[test]
+    fn breaks_start_new_lines() {
+        assert_eq!(
+            spans("one<br>two<br/>three\nfour"),
+            [
+                [plain("one")],
+                [plain("two")],
+                [plain("three")],
+                [plain("four")]
+            ]
+        );
+        assert_eq!(
+            spans("<p>one</p>two<br><br>"),
+            [[plain("one")], [plain("two")]]
+        );
+    }
+
+    #[test]
+    fn nested_tags_combine_styles() {
+        let italic = BOLD.add_modifier(Modifier::ITALIC);
+        assert_eq!(
+            spans("a<b>b<i>c</i>d</b>e"),
+            [[
+                plain("a"),
+                ("b".to_owned(), BOLD),
+                ("c".to_owned(), italic),
+                ("d".to_owned(), BOLD),
+                plain("e"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn closing_an_outer_tag_closes_the_inner_ones() {
+        assert_eq!(
+            spans("<B>a<u>b</b>c"),
+            [[
+                ("a".to_owned(), BOLD),
+                ("b".to_owned(), BOLD.add_modifier(Modifier::UNDERLINED)),
+                plain("c"),
+            ]]
+        );
+    }
+
+    #[test]
+    fn unclosed_tags_style_the_rest() {
+        assert_eq!(spans("a<b>b"), [[plain("a"), ("b".to_owned(), BOLD)]]);
+        // closing a tag that was never opened changes nothing
+        assert_eq!(
+            spans("<b>a</i>b"),
+            [[("a".to_owned(), BOLD), ("b".to_owned(), BOLD)]]
+        );
+    }
+
+    #[test]
+    fn unterminated_tag_is_text() {
+        assert_eq!(spans("1 < 2"), [[plain("1 "), plain("< 2")]]);
+        assert_eq!(spans("a<b"), [[plain("a"), plain("<b")]]);
+    }
+
+    #[test]
+    fn unknown_and_self_closing_tags_are_dropped() {
+        assert_eq!(
+            spans("<span color='red'>a</span><img src='x'/>b"),
+            [[plain("a"), plain("b")]]
+        );
+    }
+
+    #[test]
+    fn entities_are_unescaped() {
+        assert_eq!(
+            spans("&lt;b&gt; &amp; &#65;&#x42; &bogus; &"),
+            [[plain("<b> & AB &bogus; &")]]
+        );
+    }
+}
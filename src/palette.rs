@@ -0,0 +1,246 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListState, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::{config::Config, config::KeyBindEvent, search::fuzzy_match};
+
+/// State of the command palette.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    /// command name followed by its arguments
+    pub input: String,
+    /// index of the selected command
+    pub selected: usize,
+    /// error of the last command run from the palette
+    pub error: Option<String>,
+}
+
+impl Palette {
+    /// Splits the input into the command name and its arguments.
+    pub fn split_input(&self) -> (&str, &str) {
+        let input = self.input.trim_start();
+        input
+            .split_once(char::is_whitespace)
+            .map_or((input, ""), |(name, args)| (name, args.trim()))
+    }
+}
+
+/// Command that can be run from the palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Any bindable action
+    KeyBind(KeyBindEvent),
+    /// Sets the number of columns, takes the count
+    Columns,
+}
+
+impl Command {
+    /// Every command, bindable actions first.
+    pub fn all() -> Vec<Command> {
+        let mut commands: Vec<Command> = KeyBindEvent::ALL
+            .into_iter()
+            .map(Command::KeyBind)
+            .collect();
+        commands.push(Command::Columns);
+        commands
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::KeyBind(event) => event.name(),
+            Command::Columns => "columns",
+        }
+    }
+
+    /// Placeholder of the arguments the command takes.
+    pub fn args(self) -> &'static str {
+        match self {
+            Command::KeyBind(KeyBindEvent::HideItem | KeyBindEvent::UnhideItems) => "[id]",
+            Command::Columns => "<count>",
+            Command::KeyBind(_) => "",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Command::KeyBind(event) => event.description(),
+            Command::Columns => "Set the number of columns",
+        }
+    }
+}
+
+/// A command matching the typed name.
+#[derive(Debug, Clone)]
+pub struct PaletteMatch {
+    pub command: Command,
+    /// char indices of the name matching the input
+    pub matches: Vec<usize>,
+}
+
+/// Commands whose name matches the typed one, an exact match first.
+pub fn matching_commands(palette: &Palette) -> Vec<PaletteMatch> {
+    let (name, _) = palette.split_input();
+    let mut matches: Vec<(i32, PaletteMatch)> = Command::all()
+        .into_iter()
+        .filter_map(|command| {
+            let (score, matches) = fuzzy_match(name, command.name())?;
+            let score = match command.name() == name {
+                true => i32::MAX,
+                false => score,
+            };
+            Some((score, PaletteMatch { command, matches }))
+        })
+        .collect();
+    matches.sort_by_key(|(score, _)| -score);
+    matches.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Command palette with the matching commands and their key bindings.
+pub struct PaletteView<'a> {
+    pub palette: &'a Palette,
+    pub matches: &'a [PaletteMatch],
+    pub config: &'a Config,
+}
+
+impl Widget for PaletteView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let colors = &self.config.colors;
+        let matched = Style::default()
+            .fg(colors.border_fg_focused)
+            .add_modifier(Modifier::BOLD);
+        let dim = Style::default().add_modifier(Modifier::DIM);
+
+        let name_width = self
+            .matches
+            .iter()
+            .map(|m| m.command.name().len() + m.command.args().len() + 1)
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = self
+            .matches
+            .iter()
+            .map(|m| {
+                let command = m.command;
+                let mut spans: Vec<Span> = command
+                    .name()
+                    .chars()
+                    .enumerate()
+                    .map(|(i, ch)| match m.matches.contains(&i) {
+                        true => Span::styled(ch.to_string(), matched),
+                        false => Span::raw(ch.to_string()),
+                    })
+                    .collect();
+                let padding = name_width - command.name().len();
+                spans.push(Span::styled(
+                    format!(" {:padding$}  ", command.args(), padding = padding - 1),
+                    dim,
+                ));
+                spans.push(Span::raw(command.description()));
+                if let Command::KeyBind(event) = command {
+                    let keys = self.config.bindings(event);
+                    if !keys.is_empty() {
+                        spans.push(Span::styled(format!("  {}", keys.join(", ")), dim));
+                    }
+                }
+                Line::from(spans)
+            })
+            .collect();
+
+        let mut block = Block::bordered().title("Commands").border_style(
+            Style::default()
+                .fg(colors.border_fg_focused)
+                .bg(colors.border_bg_focused),
+        );
+        if let Some(error) = &self.palette.error {
+            block = block.title_bottom(Line::styled(
+                format!(" {error} "),
+                Style::default().fg(colors.fg_alert),
+            ));
+        }
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [prompt, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        Paragraph::new(format!(":{}█", self.palette.input))
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .render(prompt, buf);
+
+        let list = List::new(lines)
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .highlight_style(
+                Style::default()
+                    .fg(colors.fg_highlighted)
+                    .bg(colors.bg_highlighted),
+            );
+        let mut state = ListState::default().with_selected(Some(self.palette.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(input: &str) -> Palette {
+        Palette {
+            input: input.to_owned(),
+            ..Palette::default()
+        }
+    }
+
+    fn names(input: &str) -> Vec<&'static str> {
+        matching_commands(&palette(input))
+            .iter()
+            .map(|m| m.command.name())
+            .collect()
+    }
+
+    #[test]
+    fn input_is_split_into_name_and_arguments() {
+        assert_eq!(palette("  columns   3 ").split_input(), ("columns", "3"));
+        assert_eq!(
+            palette("hide_item chrome 1").split_input(),
+            ("hide_item", "chrome 1")
+        );
+        assert_eq!(palette("quit").split_input(), ("quit", ""));
+        assert_eq!(palette("").split_input(), ("", ""));
+    }
+
+    #[test]
+    fn exact_name_comes_first() {
+        assert_eq!(names("search")[0], "search");
+        assert_eq!(names("columns 4")[0], "columns");
+    }
+
+    #[test]
+    fn names_are_matched_fuzzily() {
+        let names = names("focus");
+        assert_eq!(names.len(), 4);
+        assert!(names.iter().all(|name| name.starts_with("focus_")));
+        let matches = matching_commands(&palette("tgm"));
+        assert_eq!(
+            matches[0].command,
+            Command::KeyBind(KeyBindEvent::ToggleMouse)
+        );
+        assert_eq!(matches[0].matches, [0, 2, 7]);
+    }
+
+    #[test]
+    fn empty_input_lists_every_command() {
+        assert_eq!(names("").len(), Command::all().len());
+        assert!(names("xyz").is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+/// Part of a parsed glob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// `*`
+    Any,
+    /// `?`
+    One,
+    Char(char),
+}
+
+/// Lowercases the pattern and splits it into tokens, `\*`, `\?` and `\\` are literal.
+fn tokens(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().flat_map(char::to_lowercase);
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => Token::Any,
+            '?' => Token::One,
+            '\\' => Token::Char(chars.next().unwrap_or('\\')),
+            c => Token::Char(c),
+        });
+    }
+    tokens
+}
+
+/// Matches `text` against a shell-like glob, ignoring case.
+///
+/// `*` matches any sequence of characters and `?` matches a single one,
+/// a backslash makes the next character literal.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = tokens(pattern);
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it matched up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(Token::Any) => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(Token::One) => {
+                p += 1;
+                t += 1;
+            }
+            Some(Token::Char(c)) if *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((bp, bt)) => {
+                    backtrack = Some((bp, bt + 1));
+                    p = bp;
+                    t = bt + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&token| token == Token::Any)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns_ignore_case() {
+        assert!(glob_match("telegram", "Telegram"));
+        assert!(glob_match("NM-APPLET", "nm-applet"));
+        assert!(!glob_match("telegram", "telegram-desktop"));
+        assert!(!glob_match("telegram-desktop", "telegram"));
+    }
+
+    #[test]
+    fn star_matches_any_sequence() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("steam*", "steam"));
+        assert!(glob_match("steam*", "steam_tray"));
+        assert!(glob_match("*tray", "steam_tray"));
+        assert!(glob_match("s*t*y", "steam_tray"));
+        assert!(glob_match("**", "x"));
+        assert!(!glob_match("steam*", "ste"));
+        assert!(!glob_match("*tray", "tray_steam"));
+    }
+
+    #[test]
+    fn star_backtracks() {
+        assert!(glob_match("*ab", "aab"));
+        assert!(glob_match("a*b*c", "abbbc"));
+        assert!(glob_match("*a*a", "banana"));
+        assert!(!glob_match("*a*b", "banana"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_char() {
+        assert!(glob_match("?", "x"));
+        assert!(glob_match("k?y", "key"));
+        assert!(glob_match("?ü?", "Müx"));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("k?y", "ky"));
+    }
+
+    #[test]
+    fn empty_pattern_matches_only_empty_text() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "x"));
+    }
+
+    #[test]
+    fn backslash_escapes_globs() {
+        assert!(glob_match("what\\?", "What?"));
+        assert!(!glob_match("what\\?", "whats"));
+        assert!(glob_match("\\*starred\\*", "*Starred*"));
+        assert!(!glob_match("\\*", "x"));
+        assert!(glob_match("a\\\\b", "a\\b"));
+        assert!(glob_match("a\\\\*", "a\\anything"));
+        // other escaped chars and a trailing backslash are literal
+        assert!(glob_match("\\x", "x"));
+        assert!(glob_match("a\\", "a\\"));
+    }
+}
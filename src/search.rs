@@ -0,0 +1,195 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListState, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::{config::Config, entries::MenuEntry};
+
+/// State of the search prompt over all tray menus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Search {
+    pub query: String,
+    /// index of the selected result
+    pub selected: usize,
+}
+
+/// A menu entry matching the search query.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub entry: MenuEntry,
+    /// path of the entry the query was matched against
+    pub path: String,
+    /// char indices of the path matching the query
+    pub matches: Vec<usize>,
+    score: i32,
+}
+
+/// Matches the query chars in order anywhere in the text, ignoring case.
+///
+/// Returns a score, higher for consecutive matches and matches at word starts,
+/// and the char indices of the matched chars.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let mut query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut matches = Vec::new();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+
+    for (i, ch) in text.chars().enumerate() {
+        let Some(wanted) = query.peek() else {
+            break;
+        };
+        if ch.to_lowercase().eq(std::iter::once(*wanted)) {
+            score += 1;
+            if matches.last().is_some_and(|last| last + 1 == i) {
+                score += 5;
+            }
+            if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 3;
+            }
+            matches.push(i);
+            query.next();
+        }
+        previous = Some(ch);
+    }
+
+    query.peek().is_none().then_some((score, matches))
+}
+
+/// Entries matching the query, best matches first.
+pub fn search(entries: Vec<MenuEntry>, query: &str) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let (score, matches) = fuzzy_match(query, &path)?;
+            Some(SearchResult {
+                entry,
+                path,
+                matches,
+                score,
+            })
+        })
+        .collect();
+    // Stable, so equal scores keep the order of the cards
+    results.sort_by_key(|result| -result.score);
+    results
+}
+
+/// Search prompt with its results.
+pub struct SearchView<'a> {
+    pub search: &'a Search,
+    pub results: &'a [SearchResult],
+    pub config: &'a Config,
+}
+
+impl Widget for SearchView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Percentage(80)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let colors = &self.config.colors;
+        let matched = Style::default()
+            .fg(colors.border_fg_focused)
+            .add_modifier(Modifier::BOLD);
+        let lines: Vec<Line> = self
+            .results
+            .iter()
+            .map(|result| {
+                let spans: Vec<Span> = result
+                    .path
+                    .chars()
+                    .enumerate()
+                    .map(|(i, ch)| match result.matches.contains(&i) {
+                        true => Span::styled(ch.to_string(), matched),
+                        false => Span::raw(ch.to_string()),
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+
+        let block = Block::bordered()
+            .title("Search")
+            .title_bottom(format!(" {} results ", self.results.len()))
+            .border_style(
+                Style::default()
+                    .fg(colors.border_fg_focused)
+                    .bg(colors.border_bg_focused),
+            );
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [prompt, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+        Paragraph::new(format!("/{}█", self.search.query))
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .render(prompt, buf);
+
+        let list = List::new(lines)
+            .style(Style::default().fg(colors.fg).bg(colors.bg))
+            .highlight_style(
+                Style::default()
+                    .fg(colors.fg_highlighted)
+                    .bg(colors.bg_highlighted),
+            );
+        let mut state = ListState::default().with_selected(Some(self.search.selected));
+        StatefulWidget::render(list, list_area, buf, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(query: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(query, text).map(|(_, matches)| matches)
+    }
+
+    fn score(query: &str, text: &str) -> i32 {
+        fuzzy_match(query, text).unwrap().0
+    }
+
+    #[test]
+    fn query_chars_match_in_order() {
+        assert_eq!(indices("vpn", "VPN"), Some(vec![0, 1, 2]));
+        assert_eq!(indices("nmw", "nm-applet › work"), Some(vec![0, 1, 12]));
+        assert_eq!(indices("npv", "VPN"), None);
+        assert_eq!(indices("vpns", "VPN"), None);
+    }
+
+    #[test]
+    fn query_ignores_case_and_whitespace() {
+        assert_eq!(indices("V p n", "vpn"), Some(vec![0, 1, 2]));
+        assert_eq!(indices("ä", "Ärger"), Some(vec![0]));
+    }
+
+    #[test]
+    fn indices_count_chars() {
+        assert_eq!(indices("w", "a › w"), Some(vec![4]));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, vec![])));
+        assert_eq!(fuzzy_match(" ", ""), Some((0, vec![])));
+    }
+
+    #[test]
+    fn consecutive_and_word_start_matches_score_higher() {
+        assert!(score("vpn", "VPN Connections") > score("vpn", "avxpxn"));
+        assert!(score("con", "VPN Connections") > score("con", "VPN Icon"));
+        assert!(score("ab", "ab") > score("ab", "a b"));
+    }
+}
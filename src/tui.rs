@@ -52,6 +52,7 @@ impl<B: Backend> Tui<B> {
     /// [`rendering`]: crate::ui::render
     pub fn draw(&mut self, app: &mut App) -> AppResult<()> {
         self.terminal.draw(|frame| ui::render(app, frame))?;
+        app.images.flush(&mut io::stdout())?;
         Ok(())
     }
 
@@ -2,12 +2,21 @@ use std::iter::repeat_n;
 
 use ratatui::{
     layout::{Constraint, Layout, Rect},
-    widgets::{Scrollbar, ScrollbarOrientation, ScrollbarState},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
 
-use crate::app::App;
-use crate::wrappers::Item;
+use crate::app::{App, Mode};
+use crate::graphics::Placement;
+use crate::help::HelpView;
+use crate::icon::ICON_WIDTH;
+use crate::inspector::InspectorView;
+use crate::markup::markup_to_lines;
+use crate::palette::{matching_commands, PaletteView};
+use crate::search::SearchView;
+use crate::wrappers::{GetGlyph, Item};
 
 /// Renders the user interface widgets.
 pub fn render(app: &mut App, frame: &mut Frame) {
@@ -20,6 +29,15 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     let min_height = app.config.min_height;
     let mut area = frame.area();
 
+    if let Some(lines) = tooltip_lines(app) {
+        // Borders around the lines, never taking more than half of the screen
+        let height = (lines.len() as u16 + 2).min(area.height / 2);
+        let [main, panel] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(height)]).areas(area);
+        area = main;
+        render_tooltip(app, frame, lines, panel);
+    }
+
     let total_min_height = rows as u16 * min_height;
 
     // Split area for scrollbar only if enabled AND needed
@@ -53,7 +71,11 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             // Snap to row boundary when auto-scrolling down
             app.layout.scroll_offset = (app.layout.scroll_offset / min_height) * min_height;
             if app.layout.scroll_offset + viewport_height < row_bottom {
-                 app.layout.scroll_offset = app.layout.scroll_offset.saturating_add(min_height).min(max_scroll);
+                app.layout.scroll_offset = app
+                    .layout
+                    .scroll_offset
+                    .saturating_add(min_height)
+                    .min(max_scroll);
             }
         }
         app.last_focused_sni_index = app.focused_sni_index;
@@ -70,11 +92,28 @@ pub fn render(app: &mut App, frame: &mut Frame) {
         frame.render_stateful_widget(scrollbar, sa, &mut state);
     }
 
+    let protocol = app.icons.protocol();
+    let mut placements = Vec::new();
     if let Some(items) = app.get_items() {
         let mut items_vec: Vec<Item> = Vec::new();
         app.sni_states.iter().for_each(|(k, v)| {
             if let Some(pair) = items.get(k) {
-                let item = Item::new(v, pair, &app.config);
+                let mut item = Item::new(v, pair, &app.config);
+                let focused = app.get_focused_sni_key() == Some(k);
+                if let (Mode::Mnemonic(_), true) = (&app.mode, focused) {
+                    item = item.with_hint(" access key ");
+                }
+                let glyph = app
+                    .config
+                    .nerd_font
+                    .then(|| pair.0.get_glyph(&app.config.glyphs))
+                    .flatten();
+                if let Some(glyph) = glyph {
+                    // Glyphs replace the icon of the items they match
+                    item = item.with_glyph(glyph);
+                } else if let (Some(icon), Some(protocol)) = (app.icons.get(k, &pair.0), protocol) {
+                    item = item.with_icon(icon, protocol);
+                }
                 items_vec.push(item);
             }
         });
@@ -113,12 +152,12 @@ pub fn render(app: &mut App, frame: &mut Frame) {
                         let y_offset = abs_y as i32 - scroll_y as i32;
 
                         if y_offset < 0 {
-                             // Item is partially above the top
-                             r.y = area.y;
-                             r.height = (abs_y + col_rect.height).saturating_sub(scroll_y);
+                            // Item is partially above the top
+                            r.y = area.y;
+                            r.height = (abs_y + col_rect.height).saturating_sub(scroll_y);
                         } else {
-                             // Item is below or at the top
-                             r.y = area.y + y_offset as u16;
+                            // Item is below or at the top
+                            r.y = area.y + y_offset as u16;
                         }
                         result.push(r.intersection(area));
                     }
@@ -128,8 +167,65 @@ pub fn render(app: &mut App, frame: &mut Frame) {
             result
         };
 
+        // Images are written by the image layer after the frame is drawn
+        for (item, rect) in items_vec.iter().zip(rectangles.iter()) {
+            if let Some((icon, protocol)) = &item.icon {
+                if protocol.is_image()
+                    && !app.mode.is_overlay()
+                    && rect.width > ICON_WIDTH + 2
+                    && rect.height > 0
+                {
+                    placements.push(Placement {
+                        x: rect.x + 1,
+                        y: rect.y,
+                        icon: icon.clone(),
+                    });
+                }
+            }
+        }
+
         render_items(frame, items_vec, rectangles.iter());
     }
+    app.images.placements = placements;
+
+    if let Mode::Inspector(inspector) = &app.mode {
+        if let Some(fields) = app.inspector_fields(inspector) {
+            let view = InspectorView {
+                fields: &fields,
+                selected: inspector.selected,
+                config: &app.config,
+            };
+            frame.render_widget(view, frame.area());
+        }
+    }
+
+    if let Mode::Help(scroll) = &mut app.mode {
+        let view = HelpView {
+            scroll,
+            config: &app.config,
+        };
+        frame.render_widget(view, frame.area());
+    }
+
+    if let Mode::Palette(palette) = &app.mode {
+        let matches = matching_commands(palette);
+        let view = PaletteView {
+            palette,
+            matches: &matches,
+            config: &app.config,
+        };
+        frame.render_widget(view, frame.area());
+    }
+
+    if let Mode::Search(search) = &app.mode {
+        let results = app.search_results(&search.query);
+        let view = SearchView {
+            search,
+            results: &results,
+            config: &app.config,
+        };
+        frame.render_widget(view, frame.area());
+    }
 
     app.sni_states
         .values_mut()
@@ -142,3 +238,37 @@ fn render_items(frame: &mut Frame, items: Vec<Item>, rects_iter: std::slice::Ite
         frame.render_widget(item, *ar);
     });
 }
+
+/// Tooltip title and description of the focused item, if it has any.
+fn tooltip_lines(app: &App) -> Option<Vec<Line<'static>>> {
+    if !app.config.tooltip {
+        return None;
+    }
+    let items = app.get_items()?;
+    let tooltip = items.get(app.get_focused_sni_key()?)?.0.tool_tip.as_ref()?;
+
+    let mut lines = Vec::new();
+    if !tooltip.title.is_empty() {
+        lines.extend(
+            markup_to_lines(&tooltip.title)
+                .into_iter()
+                .map(|line| line.patch_style(Style::default().add_modifier(Modifier::BOLD))),
+        );
+    }
+    if !tooltip.description.is_empty() {
+        lines.extend(markup_to_lines(&tooltip.description));
+    }
+    (!lines.is_empty()).then_some(lines)
+}
+
+fn render_tooltip(app: &App, frame: &mut Frame, lines: Vec<Line<'static>>, area: Rect) {
+    let colors = &app.config.colors;
+    let block = Block::bordered()
+        .title("Tooltip")
+        .border_style(Style::default().fg(colors.border_fg).bg(colors.border_bg));
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(colors.fg).bg(colors.bg))
+        .wrap(Wrap { trim: true })
+        .block(block);
+    frame.render_widget(paragraph, area);
+}
@@ -1,25 +1,35 @@
 use std::cell::RefCell;
 
-use ratatui::widgets::{Block, StatefulWidget};
+use ratatui::widgets::{Block, Paragraph, StatefulWidget, Wrap};
 use ratatui::{
     buffer::Buffer,
     layout::{self, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::Widget,
 };
 use system_tray::client::{Event, UpdateEvent};
 use system_tray::{
-    item::StatusNotifierItem,
-    menu::{MenuItem, TrayMenu},
+    item::{Status, StatusNotifierItem},
+    menu::{Disposition, MenuItem, MenuType, ToggleState, ToggleType, TrayMenu},
 };
+use unicode_width::UnicodeWidthStr;
 
 use tui_tree_widget::{Tree, TreeItem, TreeState};
 
-use crate::config::Config;
+use crate::config::{Config, GlyphRule, Symbols};
+use crate::graphics::Protocol;
+use crate::icon::{HalfBlocks, Icon, ICON_WIDTH};
+use crate::pattern::glob_match;
 
-pub type Id = usize;
+/// Tree identifier of a menu entry, the dbusmenu id of the [MenuItem].
+pub type Id = i32;
 
-#[derive(Debug)]
+/// Id of the placeholder shown inside submenus that haven't been loaded yet.
+/// dbusmenu ids are never negative, so it can't clash with a real entry.
+const LOADING_ID: Id = -1;
+
+#[derive(Debug, Default)]
 pub struct SniState {
     pub rect: Rect,
     pub focused: bool,
@@ -28,11 +38,7 @@ pub struct SniState {
 
 impl SniState {
     pub fn new() -> Self {
-        Self {
-            rect: Rect::default(),
-            focused: false,
-            tree_state: RefCell::default(),
-        }
+        Self::default()
     }
 
     pub fn set_rect(&mut self, rect: Rect) {
@@ -52,7 +58,7 @@ impl GetTitle for StatusNotifierItem {
     fn get_title(&self) -> &String {
         if let Some(title) = &self.title {
             if !title.is_empty() {
-                return &title;
+                return title;
             }
         }
 
@@ -64,6 +70,87 @@ impl GetTitle for StatusNotifierItem {
     }
 }
 
+pub trait GetGlyph {
+    /// Glyph of the first matching rule, swapped for its attention glyph while the item
+    /// needs attention.
+    fn get_glyph<'a>(&self, rules: &'a [GlyphRule]) -> Option<&'a str>;
+}
+
+impl GetGlyph for StatusNotifierItem {
+    fn get_glyph<'a>(&self, rules: &'a [GlyphRule]) -> Option<&'a str> {
+        let attention = self.status == Status::NeedsAttention;
+        let icon_name = match &self.attention_icon_name {
+            Some(name) if attention && !name.is_empty() => Some(name),
+            _ => self.icon_name.as_ref(),
+        };
+        let matches = |pattern: &Option<String>, value: Option<&String>| match pattern {
+            Some(pattern) => value.is_some_and(|value| glob_match(pattern, value)),
+            None => true,
+        };
+
+        let rule = rules.iter().find(|rule| {
+            (rule.id.is_some() || rule.icon_name.is_some())
+                && matches(&rule.id, Some(&self.id))
+                && matches(&rule.icon_name, icon_name)
+        })?;
+        match &rule.attention_glyph {
+            Some(glyph) if attention => Some(glyph),
+            _ => Some(&rule.glyph),
+        }
+    }
+}
+
+/// What activating a tray item does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemAction {
+    /// Navigate the exported dbusmenu and activate its entries.
+    Menu,
+    /// Ask the item to show its own context menu.
+    ContextMenu,
+    /// Send the primary activation to the item.
+    Activate,
+}
+
+/// Decides how to interact with an item based on its [StatusNotifierItem] properties.
+pub trait GetAction {
+    /// Whether the item exports a non-empty dbusmenu.
+    fn has_menu(&self) -> bool;
+
+    /// Action for activating inside the card.
+    /// Items without a dbusmenu fall back to their context menu.
+    fn menu_action(&self) -> ItemAction;
+
+    /// Action for the primary activation of the item itself.
+    /// Items setting `ItemIsMenu` open their menu instead.
+    fn primary_action(&self) -> ItemAction;
+}
+
+impl GetAction for (StatusNotifierItem, Option<TrayMenu>) {
+    fn has_menu(&self) -> bool {
+        self.0.menu.is_some()
+            && self
+                .1
+                .as_ref()
+                .is_some_and(|menu| !menu.submenus.is_empty())
+    }
+
+    fn menu_action(&self) -> ItemAction {
+        if self.has_menu() {
+            ItemAction::Menu
+        } else {
+            ItemAction::ContextMenu
+        }
+    }
+
+    fn primary_action(&self) -> ItemAction {
+        if self.0.item_is_menu {
+            self.menu_action()
+        } else {
+            ItemAction::Activate
+        }
+    }
+}
+
 /// Wrapper around set of [StatusNotifierItem] and [TrayMenu]
 #[derive(Debug)]
 pub struct Item<'a> {
@@ -72,6 +159,12 @@ pub struct Item<'a> {
     pub menu: &'a Option<TrayMenu>,
     config: &'a Config,
     pub rect: Rect,
+    /// short text shown at the bottom of the card, e.g. the current input mode
+    pub hint: Option<&'a str>,
+    /// icon drawn in front of the title
+    pub icon: Option<(Icon, Protocol)>,
+    /// Nerd Font glyph written in front of the title
+    pub glyph: Option<&'a str>,
 }
 
 impl<'a> Item<'a> {
@@ -86,9 +179,27 @@ impl<'a> Item<'a> {
             menu,
             config,
             rect: Rect::default(),
+            hint: None,
+            icon: None,
+            glyph: None,
         }
     }
 
+    pub fn with_glyph(mut self, glyph: &'a str) -> Self {
+        self.glyph = Some(glyph);
+        self
+    }
+
+    pub fn with_icon(mut self, icon: Icon, protocol: Protocol) -> Self {
+        self.icon = Some((icon, protocol));
+        self
+    }
+
+    pub fn with_hint(mut self, hint: &'a str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
     pub fn set_rect(&mut self, rect: Rect) {
         self.rect = rect;
     }
@@ -101,6 +212,8 @@ impl<'a> Item<'a> {
         if self.sni_state.focused {
             bg = colors.bg_focused;
             fg = colors.fg_focused;
+        } else if self.item.status == Status::Passive {
+            fg = colors.fg_passive;
         }
 
         (bg, fg)
@@ -113,23 +226,55 @@ impl<'a> Item<'a> {
 
     pub fn get_border_color(&self) -> (Color, Color) {
         let colors = &self.config.colors;
-        match self.sni_state.focused {
-            true => (colors.border_bg_focused, colors.border_fg_focused),
-            false => (colors.border_bg, colors.border_fg),
+        match (self.sni_state.focused, self.item.status) {
+            (true, _) => (colors.border_bg_focused, colors.border_fg_focused),
+            (false, Status::NeedsAttention) => (colors.border_bg, colors.border_fg_attention),
+            (false, Status::Passive) => (colors.border_bg, colors.fg_passive),
+            (false, _) => (colors.border_bg, colors.border_fg),
+        }
+    }
+
+    /// Marker of the item status, shown at the right of the title.
+    pub fn get_status_marker(&self) -> Line<'a> {
+        let symbols = &self.config.symbols;
+        match self.item.status {
+            Status::NeedsAttention => Line::styled(
+                symbols.status_attention.as_str(),
+                Style::default().fg(self.config.colors.border_fg_attention),
+            ),
+            Status::Passive => Line::raw(symbols.status_passive.as_str()),
+            _ => Line::raw(symbols.status_active.as_str()),
         }
+        .right_aligned()
     }
 }
 
 impl Widget for Item<'_> {
     fn render(self, area: layout::Rect, buf: &mut Buffer) {
-        let title = self.item.get_title().clone();
+        let mut title = self.item.get_title().clone();
+        if let Some(glyph) = self.glyph {
+            title = format!("{glyph} {title}");
+        } else if self.icon.is_some() {
+            // Leave room for the icon drawn over the start of the title
+            title.insert_str(0, &" ".repeat(ICON_WIDTH as usize + 1));
+        }
         let (bg, fg) = self.get_colors();
         let (bg_h, fg_h) = self.get_highlight_colors();
         let (border_bg, border_fg) = self.get_border_color();
         let symbols = &self.config.symbols;
 
         if let Some(menu) = self.menu {
-            let children = menuitems_to_treeitems(&menu.submenus);
+            let colors = &self.config.colors;
+            let tree_style = TreeStyle {
+                // Inside the borders and after the highlight symbol
+                width: (area.width.saturating_sub(2) as usize)
+                    .saturating_sub(symbols.highlight_symbol.width()),
+                disabled: Style::default().fg(colors.fg_disabled),
+                warning: Style::default().fg(colors.fg_warning),
+                alert: Style::default().fg(colors.fg_alert),
+                symbols,
+            };
+            let children = menuitems_to_treeitems(&menu.submenus, &tree_style, 0);
 
             let tree = Tree::new(&children);
 
@@ -141,11 +286,14 @@ impl Widget for Item<'_> {
                     .node_open_symbol(&symbols.node_open_symbol)
                     .node_closed_symbol(&symbols.node_closed_symbol)
                     .node_no_children_symbol(&symbols.node_no_children_symbol);
-                tree = tree.block(
-                    Block::bordered()
-                        .title(title)
-                        .border_style(Style::default().fg(border_fg).bg(border_bg)),
-                );
+                let mut block = Block::bordered()
+                    .title(title)
+                    .title(self.get_status_marker())
+                    .border_style(Style::default().fg(border_fg).bg(border_bg));
+                if let Some(hint) = self.hint {
+                    block = block.title_bottom(hint);
+                }
+                tree = tree.block(block);
 
                 StatefulWidget::render(
                     tree,
@@ -155,58 +303,275 @@ impl Widget for Item<'_> {
                 );
             }
         } else {
-            let block = Block::default().title(title).style(Style::default());
-            block.render(area, buf);
+            let block = Block::bordered()
+                .title(title)
+                .title(self.get_status_marker())
+                .border_style(Style::default().fg(border_fg).bg(border_bg));
+            Paragraph::new("No menu, activate to open the context menu")
+                .style(Style::default().bg(bg).fg(fg))
+                .wrap(Wrap { trim: true })
+                .block(block)
+                .render(area, buf);
+        }
+
+        if let Some((icon, protocol)) = &self.icon {
+            let icon_area = Rect::new(area.x + 1, area.y, ICON_WIDTH, 1).intersection(area);
+            match protocol {
+                Protocol::Halfblocks => HalfBlocks(icon).render(icon_area, buf),
+                // Images are drawn after the frame, ratatui must leave these cells alone
+                _ => {
+                    for position in icon_area.positions() {
+                        if let Some(cell) = buf.cell_mut(position) {
+                            cell.set_skip(true);
+                        }
+                    }
+                }
+            }
         }
     }
 }
 
-fn menuitem_to_treeitem(id: usize, menu_item: &MenuItem) -> Option<TreeItem<'_, Id>> {
-    if menu_item.submenu.is_empty() {
-        match &menu_item.label {
-            Some(label) => return Some(TreeItem::new_leaf(id, label.clone())),
-            None => return None,
+/// Styling applied to tree items while converting menu entries.
+struct TreeStyle<'a> {
+    /// Width available to top level entries, including their node symbol
+    width: usize,
+    disabled: Style,
+    warning: Style,
+    alert: Style,
+    symbols: &'a Symbols,
+}
+
+impl TreeStyle<'_> {
+    /// Width left for the text of an entry at the given depth.
+    fn text_width(&self, depth: usize, is_leaf: bool) -> usize {
+        let symbols = self.symbols;
+        let symbol_width = match is_leaf {
+            true => symbols.node_no_children_symbol.width(),
+            false => symbols
+                .node_open_symbol
+                .width()
+                .max(symbols.node_closed_symbol.width()),
+        };
+        self.width.saturating_sub(depth * 2 + symbol_width)
+    }
+
+    /// Symbol drawn in front of checkbox and radio entries.
+    fn toggle_symbol(&self, menu_item: &MenuItem) -> &str {
+        let symbols = self.symbols;
+        match (menu_item.toggle_type, menu_item.toggle_state) {
+            (ToggleType::Checkmark, ToggleState::On) => &symbols.checkbox_checked,
+            (ToggleType::Checkmark, _) => &symbols.checkbox_unchecked,
+            (ToggleType::Radio, ToggleState::On) => &symbols.radio_selected,
+            (ToggleType::Radio, _) => &symbols.radio_unselected,
+            (ToggleType::CannotBeToggled, _) => "",
+        }
+    }
+
+    fn style(&self, menu_item: &MenuItem) -> Style {
+        if !menu_item.enabled {
+            return self.disabled;
+        }
+        match menu_item.disposition {
+            Disposition::Warning => self.warning,
+            Disposition::Alert => self.alert,
+            _ => Style::default(),
+        }
+    }
+
+    fn label(&self, menu_item: &MenuItem, label: &str, text_width: usize) -> Line<'static> {
+        let mut spans = vec![Span::raw(self.toggle_symbol(menu_item).to_owned())];
+        let (label, mnemonic) = parse_mnemonic(label);
+        match mnemonic {
+            Some(index) => {
+                let (before, rest) = label.split_at(index);
+                let ch_len = rest.chars().next().map_or(0, char::len_utf8);
+                let (key, after) = rest.split_at(ch_len);
+                spans.push(Span::raw(before.to_owned()));
+                spans.push(Span::styled(
+                    key.to_owned(),
+                    Style::default().add_modifier(Modifier::UNDERLINED),
+                ));
+                spans.push(Span::raw(after.to_owned()));
+            }
+            None => spans.push(Span::raw(label)),
+        }
+
+        // Right-align the shortcut, as long as it fits next to the label
+        if let Some(shortcut) = menu_item.shortcut.as_ref().and_then(|s| format_shortcut(s)) {
+            let label_width: usize = spans.iter().map(Span::width).sum();
+            let padding = text_width.saturating_sub(label_width + shortcut.width());
+            if padding > 0 {
+                spans.push(Span::raw(" ".repeat(padding)));
+                spans.push(Span::styled(
+                    shortcut,
+                    Style::default().add_modifier(Modifier::DIM),
+                ));
+            }
         }
+
+        Line::from(spans).style(self.style(menu_item))
+    }
+}
+
+/// Formats a dbusmenu shortcut, e.g. `[["Control", "q"]]` as `Ctrl+Q`.
+fn format_shortcut(shortcut: &[Vec<String>]) -> Option<String> {
+    let chords: Vec<String> = shortcut
+        .iter()
+        .filter(|keys| !keys.is_empty())
+        .map(|keys| {
+            keys.iter()
+                .map(|key| match key.as_str() {
+                    "Control" => String::from("Ctrl"),
+                    key if key.chars().count() == 1 => key.to_uppercase(),
+                    key => key.to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+        .collect();
+
+    match chords.is_empty() {
+        true => None,
+        false => Some(chords.join(", ")),
+    }
+}
+
+fn menuitem_to_treeitem<'a>(
+    menu_item: &'a MenuItem,
+    tree_style: &TreeStyle,
+    depth: usize,
+) -> Option<TreeItem<'a, Id>> {
+    let id = menu_item.id;
+    if !menu_item.visible {
+        return None;
+    }
+
+    if menu_item.is_separator() {
+        let rule = "─".repeat(tree_style.text_width(depth, true));
+        return Some(TreeItem::new_leaf(id, rule));
+    }
+
+    if !menu_item.is_submenu() {
+        let label = menu_item.label.as_ref()?;
+        let text_width = tree_style.text_width(depth, true);
+        return Some(TreeItem::new_leaf(
+            id,
+            tree_style.label(menu_item, label, text_width),
+        ));
+    }
+    let mut children = menuitems_to_treeitems(&menu_item.submenu, tree_style, depth + 1);
+    // Only submenus the app hasn't filled in yet are loading, not ones with nothing visible
+    if menu_item.submenu.is_empty() && menu_item.children_display.as_deref() == Some("submenu") {
+        let placeholder = Span::styled("Loading…", tree_style.disabled);
+        children.push(TreeItem::new_leaf(LOADING_ID, placeholder));
     }
-    let children = menuitems_to_treeitems(&menu_item.submenu);
     let root = TreeItem::new(
         id,
-        menu_item.label.clone().unwrap_or(String::from("no_label")),
+        tree_style.label(
+            menu_item,
+            menu_item.label.as_deref().unwrap_or("no_label"),
+            tree_style.text_width(depth, false),
+        ),
         children,
     );
 
     root.ok()
 }
 
-fn menuitems_to_treeitems(menu_items: &Vec<MenuItem>) -> Vec<TreeItem<'_, Id>> {
+fn menuitems_to_treeitems<'a>(
+    menu_items: &'a [MenuItem],
+    tree_style: &TreeStyle,
+    depth: usize,
+) -> Vec<TreeItem<'a, Id>> {
     menu_items
         .iter()
-        .enumerate()
-        .map(|(index, menu_item)| menuitem_to_treeitem(index, menu_item))
-        .filter_map(|x| x)
+        .filter_map(|menu_item| menuitem_to_treeitem(menu_item, tree_style, depth))
         .collect()
 }
 
-pub trait FindMenuByUsize {
-    fn find_menu_by_usize(&self, ids: &[Id]) -> Option<&MenuItem>;
+/// Strips the dbusmenu access key markers from a label.
+///
+/// `__` stands for a literal underscore, any other underscore is dropped and the first one
+/// marks the following character as the access key.
+/// Returns the plain label and the byte index of the access key in it.
+pub fn parse_mnemonic(label: &str) -> (String, Option<usize>) {
+    let mut result = String::with_capacity(label.len());
+    let mut mnemonic = None;
+    let mut chars = label.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '_' {
+            result.push(ch);
+            continue;
+        }
+        match chars.peek() {
+            Some('_') => {
+                chars.next();
+                result.push('_');
+            }
+            Some(_) if mnemonic.is_none() => mnemonic = Some(result.len()),
+            _ => {}
+        }
+    }
+
+    (result, mnemonic)
 }
 
-impl FindMenuByUsize for TrayMenu {
-    fn find_menu_by_usize(&self, ids: &[Id]) -> Option<&MenuItem> {
-        if ids.len() == 0 {
-            return None;
-        }
-        let mut result: &MenuItem = self.submenus.get(ids[0])?;
-        let mut submenus = &result.submenu;
-        for i in ids.iter().skip(1) {
-            result = submenus.get(*i)?;
-            submenus = &result.submenu;
+pub trait MenuItemKind {
+    /// Whether the entry is a separator, which can't be selected.
+    fn is_separator(&self) -> bool;
+
+    /// Whether the entry can be activated by the user.
+    fn is_activatable(&self) -> bool;
+
+    /// Whether the entry opens a submenu, which may not have been loaded yet.
+    fn is_submenu(&self) -> bool;
+
+    /// Access key of the entry, see [parse_mnemonic].
+    fn mnemonic(&self) -> Option<char>;
+}
+
+impl MenuItemKind for MenuItem {
+    fn is_separator(&self) -> bool {
+        self.menu_type == MenuType::Separator
+    }
+
+    fn is_activatable(&self) -> bool {
+        self.visible && self.enabled && !self.is_separator()
+    }
+
+    fn is_submenu(&self) -> bool {
+        !self.submenu.is_empty() || self.children_display.as_deref() == Some("submenu")
+    }
+
+    fn mnemonic(&self) -> Option<char> {
+        let (label, index) = parse_mnemonic(self.label.as_ref()?);
+        label[index?..].chars().next()
+    }
+}
+
+pub trait FindMenuById {
+    /// Finds the entry by the path of dbusmenu ids leading to it.
+    fn find_menu_by_id(&self, ids: &[Id]) -> Option<&MenuItem>;
+}
+
+impl FindMenuById for TrayMenu {
+    fn find_menu_by_id(&self, ids: &[Id]) -> Option<&MenuItem> {
+        let (first, rest) = ids.split_first()?;
+
+        let mut result = find_by_id(&self.submenus, *first)?;
+        for id in rest {
+            result = find_by_id(&result.submenu, *id)?;
         }
 
         Some(result)
     }
 }
 
+fn find_by_id(items: &[MenuItem], id: Id) -> Option<&MenuItem> {
+    items.iter().find(|item| item.id == id)
+}
+
 pub struct LoggableEvent<'a>(pub &'a system_tray::client::Event);
 
 impl std::fmt::Display for LoggableEvent<'_> {
@@ -216,7 +581,7 @@ impl std::fmt::Display for LoggableEvent<'_> {
                 write!(
                     f,
                     "{} Update Event for {}",
-                    update_event_variant(&update_event),
+                    update_event_variant(update_event),
                     dest
                 )
             }
@@ -242,3 +607,393 @@ fn update_event_variant(event: &UpdateEvent) -> &'static str {
         UpdateEvent::MenuConnect(_) => "MenuConnect",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, item, menu, separator, submenu, with_menu};
+
+    #[test]
+    fn items_with_a_menu_navigate_it() {
+        let pair = with_menu(item("nm-applet"), vec![entry(1, "Wi-Fi")]);
+        assert!(pair.has_menu());
+        assert_eq!(pair.menu_action(), ItemAction::Menu);
+        assert_eq!(pair.primary_action(), ItemAction::Activate);
+    }
+
+    #[test]
+    fn items_without_a_menu_fall_back_to_the_context_menu() {
+        let pair = (item("steam"), None);
+        assert!(!pair.has_menu());
+        assert_eq!(pair.menu_action(), ItemAction::ContextMenu);
+
+        // A menu path with an empty layout is no menu either
+        let pair = with_menu(item("steam"), vec![]);
+        assert!(!pair.has_menu());
+        assert_eq!(pair.menu_action(), ItemAction::ContextMenu);
+    }
+
+    #[test]
+    fn item_is_menu_opens_the_menu_on_primary_activation() {
+        let mut pair = with_menu(item("udiskie"), vec![entry(1, "Mount")]);
+        pair.0.item_is_menu = true;
+        assert_eq!(pair.primary_action(), ItemAction::Menu);
+
+        let mut pair = (item("udiskie"), None);
+        pair.0.item_is_menu = true;
+        assert_eq!(pair.primary_action(), ItemAction::ContextMenu);
+    }
+
+    #[test]
+    fn mnemonic_is_the_first_underscored_char() {
+        assert_eq!(parse_mnemonic("_File"), ("File".to_owned(), Some(0)));
+        assert_eq!(parse_mnemonic("Save _As"), ("Save As".to_owned(), Some(5)));
+        assert_eq!(
+            parse_mnemonic("_Open _Recent"),
+            ("Open Recent".to_owned(), Some(0))
+        );
+    }
+
+    #[test]
+    fn double_underscore_is_a_literal_underscore() {
+        assert_eq!(
+            parse_mnemonic("snake__case"),
+            ("snake_case".to_owned(), None)
+        );
+        assert_eq!(parse_mnemonic("a__b_c"), ("a_bc".to_owned(), Some(3)));
+        assert_eq!(parse_mnemonic("____"), ("__".to_owned(), None));
+    }
+
+    #[test]
+    fn mnemonic_index_is_in_bytes() {
+        assert_eq!(parse_mnemonic("Ünd _Ö"), ("Ünd Ö".to_owned(), Some(5)));
+    }
+
+    #[test]
+    fn trailing_underscore_is_dropped() {
+        assert_eq!(parse_mnemonic("Quit_"), ("Quit".to_owned(), None));
+        assert_eq!(parse_mnemonic(""), (String::new(), None));
+    }
+
+    fn shortcut(chords: &[&[&str]]) -> Vec<Vec<String>> {
+        chords
+            .iter()
+            .map(|keys| keys.iter().map(|key| key.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn shortcut_keys_are_joined() {
+        let keys = shortcut(&[&["Control", "Shift", "q"]]);
+        assert_eq!(format_shortcut(&keys).as_deref(), Some("Ctrl+Shift+Q"));
+        let keys = shortcut(&[&["Alt", "F4"]]);
+        assert_eq!(format_shortcut(&keys).as_deref(), Some("Alt+F4"));
+    }
+
+    #[test]
+    fn shortcut_chords_are_listed() {
+        let keys = shortcut(&[&["Control", "x"], &[], &["Control", "s"]]);
+        assert_eq!(format_shortcut(&keys).as_deref(), Some("Ctrl+X, Ctrl+S"));
+    }
+
+    #[test]
+    fn empty_shortcut_is_none() {
+        assert_eq!(format_shortcut(&[]), None);
+        assert_eq!(format_shortcut(&shortcut(&[&[]])), None);
+    }
+
+    fn tree_style(symbols: &Symbols) -> TreeStyle<'_> {
+        TreeStyle {
+            width: 12,
+            disabled: Style::default(),
+            warning: Style::default(),
+            alert: Style::default(),
+            symbols,
+        }
+    }
+
+    #[test]
+    fn separators_are_not_activatable() {
+        assert!(separator(1).is_separator());
+        assert!(!separator(1).is_activatable());
+        assert!(entry(2, "Quit").is_activatable());
+    }
+
+    #[test]
+    fn hidden_and_disabled_entries_are_not_activatable() {
+        let hidden = MenuItem {
+            visible: false,
+            ..entry(1, "Quit")
+        };
+        let disabled = MenuItem {
+            enabled: false,
+            ..entry(2, "Quit")
+        };
+        assert!(!hidden.is_activatable());
+        assert!(!disabled.is_activatable());
+    }
+
+    #[test]
+    fn hidden_entries_are_not_drawn() {
+        let symbols = Symbols::default();
+        let hidden = MenuItem {
+            visible: false,
+            ..entry(1, "Quit")
+        };
+        let items = [hidden, separator(2), entry(3, "Quit")];
+        let tree_items = menuitems_to_treeitems(&items, &tree_style(&symbols), 0);
+        let ids: Vec<Id> = tree_items.iter().map(|item| *item.identifier()).collect();
+        assert_eq!(ids, [2, 3]);
+    }
+
+    #[test]
+    fn separator_rule_fills_the_text_width() {
+        let symbols = Symbols::default();
+        let style = tree_style(&symbols);
+        let items = [separator(1)];
+        let tree_items = menuitems_to_treeitems(&items, &style, 0);
+        let area = Rect::new(0, 0, 12, 1);
+        let mut buffer = Buffer::empty(area);
+        let tree = Tree::new(&tree_items)
+            .unwrap()
+            .node_no_children_symbol(&symbols.node_no_children_symbol);
+        StatefulWidget::render(tree, area, &mut buffer, &mut TreeState::default());
+
+        let line: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        let rule = "─".repeat(style.text_width(0, true));
+        assert_eq!(line, format!("{}{rule}", symbols.node_no_children_symbol));
+    }
+
+    fn toggle(toggle_type: ToggleType, toggle_state: ToggleState) -> MenuItem {
+        MenuItem {
+            toggle_type,
+            toggle_state,
+            ..entry(1, "Mute")
+        }
+    }
+
+    #[test]
+    fn checkboxes_show_their_state() {
+        let symbols = Symbols::default();
+        let style = tree_style(&symbols);
+        let checked = toggle(ToggleType::Checkmark, ToggleState::On);
+        let unchecked = toggle(ToggleType::Checkmark, ToggleState::Off);
+        assert_eq!(style.toggle_symbol(&checked), symbols.checkbox_checked);
+        assert_eq!(style.toggle_symbol(&unchecked), symbols.checkbox_unchecked);
+    }
+
+    #[test]
+    fn radio_entries_show_their_state() {
+        let symbols = Symbols::default();
+        let style = tree_style(&symbols);
+        let selected = toggle(ToggleType::Radio, ToggleState::On);
+        let indeterminate = toggle(ToggleType::Radio, ToggleState::Indeterminate);
+        assert_eq!(style.toggle_symbol(&selected), symbols.radio_selected);
+        assert_eq!(
+            style.toggle_symbol(&indeterminate),
+            symbols.radio_unselected
+        );
+    }
+
+    #[test]
+    fn plain_entries_have_no_toggle_symbol() {
+        let symbols = Symbols::default();
+        let style = tree_style(&symbols);
+        let plain = toggle(ToggleType::CannotBeToggled, ToggleState::On);
+        assert_eq!(style.toggle_symbol(&plain), "");
+        assert_eq!(style.label(&plain, "Mute", 12).to_string(), "Mute");
+    }
+
+    #[test]
+    fn toggle_symbol_precedes_the_label() {
+        let symbols = Symbols::default();
+        let style = tree_style(&symbols);
+        let checked = toggle(ToggleType::Checkmark, ToggleState::On);
+        let line = style.label(&checked, "Mute", 12).to_string();
+        assert_eq!(line, format!("{}Mute", symbols.checkbox_checked));
+    }
+
+    fn nested_menu() -> TrayMenu {
+        menu(vec![
+            entry(1, "Open"),
+            submenu(
+                2,
+                "Recent",
+                vec![
+                    entry(3, "a.txt"),
+                    submenu(4, "More", vec![entry(5, "b.txt")]),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn menu_entries_are_found_by_id_path() {
+        let menu = nested_menu();
+        let label = |ids: &[Id]| menu.find_menu_by_id(ids)?.label.clone();
+        assert_eq!(label(&[1]).as_deref(), Some("Open"));
+        assert_eq!(label(&[2, 3]).as_deref(), Some("a.txt"));
+        assert_eq!(label(&[2, 4, 5]).as_deref(), Some("b.txt"));
+    }
+
+    #[test]
+    fn id_paths_must_follow_the_nesting() {
+        let menu = nested_menu();
+        assert!(menu.find_menu_by_id(&[3]).is_none());
+        assert!(menu.find_menu_by_id(&[1, 3]).is_none());
+        assert!(menu.find_menu_by_id(&[2, 5]).is_none());
+        assert!(menu.find_menu_by_id(&[]).is_none());
+    }
+
+    fn child_ids(menu_item: &MenuItem) -> Vec<Id> {
+        let symbols = Symbols::default();
+        let tree_item = menuitem_to_treeitem(menu_item, &tree_style(&symbols), 0).unwrap();
+        tree_item
+            .children()
+            .iter()
+            .map(|child| *child.identifier())
+            .collect()
+    }
+
+    #[test]
+    fn entries_with_children_are_submenus() {
+        assert!(submenu(1, "Recent", vec![]).is_submenu());
+        let filled = MenuItem {
+            submenu: vec![entry(2, "a.txt")],
+            ..entry(1, "Recent")
+        };
+        assert!(filled.is_submenu());
+        assert!(!entry(1, "Recent").is_submenu());
+    }
+
+    #[test]
+    fn unloaded_submenus_show_a_placeholder() {
+        assert_eq!(child_ids(&submenu(1, "Recent", vec![])), [LOADING_ID]);
+        let loaded = submenu(1, "Recent", vec![entry(2, "a.txt")]);
+        assert_eq!(child_ids(&loaded), [2]);
+    }
+
+    #[test]
+    fn submenus_without_visible_children_are_not_loading() {
+        let hidden = MenuItem {
+            visible: false,
+            ..entry(2, "a.txt")
+        };
+        assert!(child_ids(&submenu(1, "Recent", vec![hidden])).is_empty());
+    }
+
+    fn disposition(disposition: Disposition, enabled: bool) -> MenuItem {
+        MenuItem {
+            disposition,
+            enabled,
+            ..entry(1, "Update")
+        }
+    }
+
+    #[test]
+    fn dispositions_pick_their_style() {
+        let symbols = Symbols::default();
+        let style = TreeStyle {
+            disabled: Style::default().fg(Color::DarkGray),
+            warning: Style::default().fg(Color::Yellow),
+            alert: Style::default().fg(Color::Red),
+            ..tree_style(&symbols)
+        };
+        let normal = disposition(Disposition::Normal, true);
+        let informative = disposition(Disposition::Informative, true);
+        assert_eq!(style.style(&normal), Style::default());
+        assert_eq!(style.style(&informative), Style::default());
+        assert_eq!(
+            style.style(&disposition(Disposition::Warning, true)),
+            style.warning
+        );
+        assert_eq!(
+            style.style(&disposition(Disposition::Alert, true)),
+            style.alert
+        );
+    }
+
+    #[test]
+    fn disabled_style_wins_over_disposition() {
+        let symbols = Symbols::default();
+        let style = TreeStyle {
+            disabled: Style::default().fg(Color::DarkGray),
+            alert: Style::default().fg(Color::Red),
+            ..tree_style(&symbols)
+        };
+        let item = disposition(Disposition::Alert, false);
+        assert_eq!(style.style(&item), style.disabled);
+        assert_eq!(style.label(&item, "Update", 12).style, style.disabled);
+    }
+
+    fn with_status(status: Status) -> (StatusNotifierItem, Option<TrayMenu>) {
+        let item = StatusNotifierItem {
+            status,
+            ..item("nm-applet")
+        };
+        (item, None)
+    }
+
+    #[test]
+    fn passive_items_are_dimmed() {
+        let config = Config::default();
+        let colors = &config.colors;
+        let state = SniState::new();
+        let pair = with_status(Status::Passive);
+        let card = Item::new(&state, &pair, &config);
+        assert_eq!(card.get_colors(), (colors.bg, colors.fg_passive));
+        assert_eq!(
+            card.get_border_color(),
+            (colors.border_bg, colors.fg_passive)
+        );
+    }
+
+    #[test]
+    fn attention_is_shown_on_the_border_and_marker() {
+        let config = Config::default();
+        let colors = &config.colors;
+        let state = SniState::new();
+        let pair = with_status(Status::NeedsAttention);
+        let card = Item::new(&state, &pair, &config);
+        assert_eq!(card.get_border_color().1, colors.border_fg_attention);
+        let marker = card.get_status_marker();
+        assert_eq!(marker.to_string(), config.symbols.status_attention);
+        assert_eq!(marker.style.fg, Some(colors.border_fg_attention));
+        assert_eq!(marker.alignment, Some(layout::Alignment::Right));
+    }
+
+    #[test]
+    fn focus_wins_over_status() {
+        let config = Config::default();
+        let colors = &config.colors;
+        let state = SniState {
+            focused: true,
+            ..SniState::new()
+        };
+        for status in [Status::Active, Status::Passive, Status::NeedsAttention] {
+            let pair = with_status(status);
+            let card = Item::new(&state, &pair, &config);
+            assert_eq!(card.get_colors(), (colors.bg_focused, colors.fg_focused));
+            let border = (colors.border_bg_focused, colors.border_fg_focused);
+            assert_eq!(card.get_border_color(), border);
+        }
+    }
+
+    #[test]
+    fn active_items_use_the_normal_colors() {
+        let config = Config::default();
+        let colors = &config.colors;
+        let state = SniState::new();
+        let pair = with_status(Status::Active);
+        let card = Item::new(&state, &pair, &config);
+        assert_eq!(card.get_colors(), (colors.bg, colors.fg));
+        assert_eq!(
+            card.get_border_color(),
+            (colors.border_bg, colors.border_fg)
+        );
+        assert_eq!(
+            card.get_status_marker().to_string(),
+            config.symbols.status_active
+        );
+    }
+}